@@ -0,0 +1,163 @@
+use crate::{
+    body::Body,
+    ic::center_of_mass,
+    simulation::{brute_force::BruteForce, Simulation},
+};
+
+/// Relative force error of each body against a reference solver, with its
+/// distance from the center of mass of the massive bodies.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ForceErrors {
+    // `(radius, error)` pairs sorted by error.
+    samples: Vec<(f64, f64)>,
+}
+
+impl ForceErrors {
+    /// Compares the forces `simulation` puts on `bodies` with Newtonian
+    /// direct summation.
+    pub fn measure<S: Simulation>(bodies: &[Body], g: f64, simulation: &mut S) -> ForceErrors {
+        ForceErrors::against(bodies, &mut BruteForce::new(g), simulation)
+    }
+
+    /// Steps copies of `bodies` by zero time with both solvers and compares
+    /// the resulting forces. Bodies feeling no reference force are left out.
+    pub fn against<R: Simulation, S: Simulation>(
+        bodies: &[Body],
+        reference: &mut R,
+        simulation: &mut S,
+    ) -> ForceErrors {
+        let forces = |simulation: &mut dyn Simulation| {
+            let mut bodies = bodies.to_vec();
+            simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+            bodies
+        };
+        let expected = forces(reference);
+        let actual = forces(simulation);
+
+        let (center, _) = center_of_mass(bodies);
+        let mut samples: Vec<(f64, f64)> = expected
+            .iter()
+            .zip(&actual)
+            .filter(|(expected, _)| expected.force().length() > 0.0)
+            .map(|(expected, actual)| {
+                (
+                    expected.pos().dist(&center),
+                    actual.force().dist(expected.force()) / expected.force().length(),
+                )
+            })
+            .collect();
+        samples.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        ForceErrors { samples }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Smallest error at least `percent` percent of the bodies stay within.
+    pub fn percentile(&self, percent: f64) -> f64 {
+        assert!(!self.is_empty());
+
+        let rank = (percent / 100.0 * self.len() as f64).ceil() as usize;
+        self.samples[rank.max(1).min(self.len()) - 1].1
+    }
+
+    pub fn median(&self) -> f64 {
+        self.percentile(50.0)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.percentile(100.0)
+    }
+
+    /// Splits the bodies by distance from the center into `bins` groups of
+    /// equal size, each paired with the largest radius in it. Groups left
+    /// empty by asking for more bins than bodies are skipped.
+    pub fn by_radius(&self, bins: usize) -> Vec<(f64, ForceErrors)> {
+        let mut by_radius = self.samples.clone();
+        by_radius.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        (0..bins)
+            .map(|bin| {
+                (
+                    bin * by_radius.len() / bins,
+                    (bin + 1) * by_radius.len() / bins,
+                )
+            })
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| {
+                let radius = by_radius[end - 1].0;
+                let mut samples = by_radius[start..end].to_vec();
+                samples.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+                (radius, ForceErrors { samples })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        simulation::barnes_hut::{quad::Quad, BarnesHut},
+        vec2::Vec2,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn ranks_errors() {
+        let errors = ForceErrors {
+            samples: (1..=10).map(|i| (10.0 - i as f64, i as f64)).collect(),
+        };
+
+        assert_eq!(errors.median(), 5.0);
+        assert_eq!(errors.percentile(99.0), 10.0);
+        assert_eq!(errors.percentile(0.0), 1.0);
+        assert_eq!(errors.max(), 10.0);
+
+        let by_radius = errors.by_radius(2);
+        assert_eq!(by_radius[0].0, 4.0);
+        assert_eq!(by_radius[0].1.max(), 10.0);
+        assert_eq!(by_radius[1].0, 9.0);
+        assert_eq!(by_radius[1].1.max(), 5.0);
+
+        let by_radius = errors.by_radius(15);
+        assert_eq!(by_radius.len(), 10);
+        assert!(by_radius.iter().all(|(_, errors)| errors.len() == 1));
+    }
+
+    #[test]
+    fn shrinks_with_opening_angle() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let bodies: Vec<Body> = (0..300)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-50.0, 50.0), rng.gen_range(-50.0, 50.0)),
+                    Vec2::zero(),
+                    rng.gen_range(1.0, 10.0),
+                )
+            })
+            .collect();
+
+        let errors = |opening_angle: f64| {
+            let mut simulation = BarnesHut::new(Quad::new(Vec2::zero(), 100.0), 1.0)
+                .with_opening_angle(opening_angle);
+            ForceErrors::measure(&bodies, 1.0, &mut simulation)
+        };
+
+        let exact = errors(0.0);
+        assert_eq!(exact.len(), bodies.len());
+        assert!(exact.max() < 1e-12);
+
+        let (loose, tight) = (errors(1.0), errors(0.3));
+        assert!(tight.median() < loose.median());
+        assert!(tight.percentile(99.0) < loose.percentile(99.0));
+        assert!(tight.median() < 1e-2);
+    }
+}
@@ -1,29 +1,72 @@
-use rand::prelude::*;
 use rgx::core::*;
 use rgx::kit::shape2d::{Batch, Fill, Shape, Stroke};
 use rgx::kit::{self, ZDepth};
 use rgx::math::*;
-use std::f64::consts::PI;
 use winit::{
-    event::{ElementState, Event, KeyboardInput, StartCause, VirtualKeyCode, WindowEvent},
+    event::{
+        ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, StartCause,
+        VirtualKeyCode, WindowEvent,
+    },
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
 
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
 use nbody::{
-    body::{Body, G},
+    accuracy::ForceErrors,
+    body::Body,
+    ic::{
+        center_of_mass,
+        disk::{Disk, Profile},
+        Generator,
+    },
+    render::{gif::GifEncoder, png::write_png, Camera, Canvas, Color, BLACK},
     simulation::{
-        barnes_hut::{quad::Quad, BarnesHut},
+        barnes_hut::{
+            export::{write_dot, write_svg},
+            quad::Quad,
+            tree::BarnesHutTree,
+            BarnesHut,
+        },
         Simulation,
     },
+    units::{UnitSystem, SOLAR_MASS},
     vec2::Vec2,
 };
 
-const SOLAR_MASS: f64 = 1.98892e30;
-
 struct ColorfulBody(Body, Rgba);
 
+/// What the viewer keeps in the middle of the window.
+#[derive(Clone, Copy)]
+enum Follow {
+    Nothing,
+    CenterOfMass,
+    Body(usize),
+}
+
+// Cursor travel in pixels that turns a click into a drag.
+const DRAG_THRESHOLD: f64 = 3.0;
+// Largest distance in pixels from a click to the body it selects.
+const SELECT_RADIUS: f64 = 10.0;
+const ZOOM_PER_LINE: f64 = 1.2;
+const PIXELS_PER_LINE: f64 = 20.0;
+
 fn main() -> Result<(), std::io::Error> {
+    match std::env::args().nth(1).as_deref() {
+        Some("accuracy") => {
+            report_accuracy(seed_arg(2));
+            return Ok(());
+        }
+        Some("tree") => return export_tree(seed_arg(2)),
+        Some("render") => return render_frames(seed_arg(2), frames_arg(3)),
+        Some("gif") => return render_gif(seed_arg(2), frames_arg(3)),
+        _ => {}
+    }
+
     let event_loop = EventLoop::new();
     let window = Window::new(&event_loop).unwrap();
 
@@ -34,8 +77,16 @@ fn main() -> Result<(), std::io::Error> {
 
     let mut textures = r.swap_chain(win.width as u32, win.height as u32, PresentMode::default());
 
-    let mut sim = BarnesHut::new(Quad::new(Vec2::zero(), 2.0 * 1e18));
-    let mut bodies = create_bodies(5000);
+    let g = UnitSystem::si().gravitational_constant();
+    let mut sim = BarnesHut::new(Quad::new(Vec2::zero(), 2.0 * 1e18), g);
+    let mut bodies = create_bodies(5000, seed_arg(1), g);
+
+    let mut camera = Camera::new(Vec2::zero(), 1e18);
+    let mut follow = Follow::Nothing;
+    let mut cursor = (0.0, 0.0);
+    // Where the left button went down and whether the cursor moved since.
+    let mut drag: Option<(f64, f64)> = None;
+    let mut dragged = false;
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::NewEvents(StartCause::Init) => {
@@ -52,9 +103,13 @@ fn main() -> Result<(), std::io::Error> {
                     },
                 ..
             } => match key {
-                VirtualKeyCode::Escape => {
-                    *control_flow = ControlFlow::Exit;
+                VirtualKeyCode::Escape => *control_flow = ControlFlow::Exit,
+                VirtualKeyCode::F => {
+                    follow = Follow::Nothing;
+                    camera.fit(bodies.iter().map(|body| body.0.pos()), 0.1);
                 }
+                VirtualKeyCode::C => follow = Follow::CenterOfMass,
+                VirtualKeyCode::N => follow = Follow::Nothing,
                 _ => {}
             },
             WindowEvent::CloseRequested => {
@@ -66,9 +121,59 @@ fn main() -> Result<(), std::io::Error> {
                 let (w, h) = (win.width as u32, win.height as u32);
                 textures = r.swap_chain(w, h, PresentMode::default());
             }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = position.to_physical(window.hidpi_factor());
+                if let Some((x, y)) = drag {
+                    let (dx, dy) = (position.x - cursor.0, position.y - cursor.1);
+                    if (position.x - x).hypot(position.y - y) > DRAG_THRESHOLD {
+                        dragged = true;
+                    }
+                    if dragged {
+                        follow = Follow::Nothing;
+                        camera.pan(dx, dy, win.width, win.height);
+                    }
+                }
+                cursor = (position.x, position.y);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y as f64,
+                    MouseScrollDelta::PixelDelta(position) => position.y / PIXELS_PER_LINE,
+                };
+                let factor = ZOOM_PER_LINE.powf(lines);
+                camera.zoom(factor, cursor.0, cursor.1, win.width, win.height);
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => match state {
+                ElementState::Pressed => {
+                    drag = Some(cursor);
+                    dragged = false;
+                }
+                ElementState::Released => {
+                    if drag.take().is_some() && !dragged {
+                        follow = select_body(&bodies, &camera, cursor, win.width, win.height)
+                            .map_or(Follow::Nothing, Follow::Body);
+                    }
+                }
+            },
             WindowEvent::RedrawRequested => {
+                match follow {
+                    Follow::Nothing => {}
+                    Follow::CenterOfMass => {
+                        camera.set_center(center_of_mass(bodies.iter().map(|body| &body.0)).0)
+                    }
+                    Follow::Body(index) => camera.set_center(bodies[index].0.pos().clone()),
+                }
+
                 let (w, h) = (win.width, win.height);
-                let batch = create_batch(&bodies, w, h);
+                let selected = match follow {
+                    Follow::Body(index) => Some(index),
+                    _ => None,
+                };
+                let batch = create_batch(&bodies, &camera, w, h, selected);
 
                 let buffer = batch.finish(&r);
 
@@ -96,77 +201,211 @@ fn main() -> Result<(), std::io::Error> {
     });
 }
 
-fn create_batch(bodies: &Vec<ColorfulBody>, w: f64, h: f64) -> Batch {
-    let mut batch = Batch::new();
+fn select_body(
+    bodies: &[ColorfulBody],
+    camera: &Camera,
+    (x, y): (f64, f64),
+    w: f64,
+    h: f64,
+) -> Option<usize> {
+    bodies
+        .iter()
+        .enumerate()
+        .map(|(index, body)| {
+            let (bx, by) = camera.to_screen(body.0.pos(), w, h);
+            (index, (bx - x).hypot(by - y))
+        })
+        .filter(|&(_, dist)| dist <= SELECT_RADIUS)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(index, _)| index)
+}
 
-    for body in bodies {
-        batch.add(Shape::Circle(
-            Point2::new(
-                ((body.0.pos().x() * w / 1e18) + w / 2.0) as f32,
-                ((body.0.pos().y() * h / 1e18) + h / 2.0) as f32,
-            ),
-            ZDepth::ZERO,
-            2.0,
-            32,
-            Stroke::NONE,
-            Fill::Solid(body.1),
-        ));
-    }
+fn seed_arg(position: usize) -> u64 {
+    let seed = std::env::args()
+        .nth(position)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(rand::random);
+    eprintln!("seed: {}", seed);
 
-    batch
+    seed
 }
 
-fn create_bodies(size: usize) -> Vec<ColorfulBody> {
-    assert!(size > 0);
+fn frames_arg(position: usize) -> usize {
+    std::env::args()
+        .nth(position)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(100)
+}
 
-    let mut bodies = Vec::with_capacity(size);
+fn report_accuracy(seed: u64) {
+    let g = UnitSystem::si().gravitational_constant();
+    let bodies: Vec<Body> = create_bodies(2000, seed, g)
+        .into_iter()
+        .map(|body| body.0)
+        .collect();
+    let extent = bodies
+        .iter()
+        .map(|body| body.pos().x().abs().max(body.pos().y().abs()))
+        .fold(0.0, f64::max);
 
-    bodies.push(ColorfulBody(
-        Body::new(Vec2::zero(), Vec2::zero(), 1e6 * SOLAR_MASS),
-        Rgba::new(255.0, 0.0, 0.0, 1.0),
-    ));
+    for &opening_angle in [0.3, 0.5, 0.7, 1.0, 2.0].iter() {
+        let mut sim = BarnesHut::new(Quad::new(Vec2::zero(), 2.0 * extent), g)
+            .with_opening_angle(opening_angle);
+        let errors = ForceErrors::measure(&bodies, g, &mut sim);
 
-    for _ in 1..size {
-        let pos = Vec2::new(
-            1e18 * -((1.0 - rand_0_1()).ln()) / -1.8 * (0.5 - rand_0_1()),
-            1e18 * -((1.0 - rand_0_1()).ln()) / -1.8 * (0.5 - rand_0_1()),
+        println!(
+            "theta {:.1}: median {:.3e}, 99th {:.3e}, max {:.3e}",
+            opening_angle,
+            errors.median(),
+            errors.percentile(99.0),
+            errors.max()
         );
-        let magv = circlev(&pos);
-        let abs_angle = (pos.x() / pos.y()).abs().atan();
-        let thetav = PI / 2.0 - abs_angle;
-        let velocity = {
-            let velocity = Vec2::new(
-                -1.0 * pos.y().signum() * thetav.cos() * magv,
-                pos.x().signum() * thetav.sin() * magv,
+        for (radius, errors) in errors.by_radius(5) {
+            println!(
+                "  r <= {:.3e}: median {:.3e}, 99th {:.3e}, max {:.3e}",
+                radius,
+                errors.median(),
+                errors.percentile(99.0),
+                errors.max()
             );
+        }
+    }
+}
 
-            if random() {
-                velocity * -1.0
-            } else {
-                velocity
-            }
-        };
+fn export_tree(seed: u64) -> Result<(), std::io::Error> {
+    let g = UnitSystem::si().gravitational_constant();
+    let mut tree = BarnesHutTree::new(Quad::new(Vec2::zero(), 2.0 * 1e18));
+    for body in create_bodies(500, seed, g) {
+        if tree.quad().contains(body.0.pos()) {
+            tree.insert(&body.0);
+        }
+    }
 
-        let mass = rand_0_1() * SOLAR_MASS * 10.0 + 1e20;
+    let mut svg = BufWriter::new(File::create("tree.svg")?);
+    write_svg(&tree, 1024.0, &mut svg)?;
+    svg.flush()?;
 
-        bodies.push(ColorfulBody(
-            Body::new(pos, velocity, mass),
-            Rgba::new(
-                (mass * 254.0 / (SOLAR_MASS * 10.0 + 1e20)) as f32,
-                (mass * 254.0 / (SOLAR_MASS * 10.0 + 1e20)) as f32,
-                255.0,
-                1.0,
-            ),
-        ));
+    let mut dot = BufWriter::new(File::create("tree.dot")?);
+    write_dot(&tree, &mut dot)?;
+    dot.flush()?;
+    eprintln!("wrote tree.svg and tree.dot");
+
+    Ok(())
+}
+
+fn render_frames(seed: u64, frames: usize) -> Result<(), std::io::Error> {
+    std::fs::create_dir_all("frames")?;
+    run_headless(seed, frames, |frame, canvas| {
+        let path = format!("frames/frame_{:05}.png", frame);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        write_png(canvas, &mut writer)?;
+        writer.flush()
+    })?;
+    eprintln!("wrote {} frames to frames/", frames);
+
+    Ok(())
+}
+
+fn render_gif(seed: u64, frames: usize) -> Result<(), std::io::Error> {
+    let mut encoder = GifEncoder::new(BufWriter::new(File::create("run.gif")?), 400, 400)
+        .with_frame_rate(25.0)
+        .with_frame_skip(1);
+    run_headless(seed, frames, |_, canvas| encoder.add_frame(canvas))?;
+    encoder.finish()?;
+    eprintln!("wrote {} frames to run.gif", frames);
+
+    Ok(())
+}
+
+// Steps the viewer's simulation `frames` times, drawing each state first.
+fn run_headless<F>(seed: u64, frames: usize, mut on_frame: F) -> Result<(), std::io::Error>
+where
+    F: FnMut(usize, &Canvas) -> Result<(), std::io::Error>,
+{
+    let g = UnitSystem::si().gravitational_constant();
+    let mut sim = BarnesHut::new(Quad::new(Vec2::zero(), 2.0 * 1e18), g);
+    let mut bodies = create_bodies(5000, seed, g);
+    let camera = Camera::new(Vec2::zero(), 1e18);
+    let mut canvas = Canvas::new(800, 800);
+
+    for frame in 0..frames {
+        canvas.clear(BLACK);
+        canvas.draw_bodies(
+            bodies.iter().map(|body| (&body.0, to_color(body.1))),
+            &camera,
+            2.0,
+        );
+        on_frame(frame, &canvas)?;
+
+        sim.step(&mut bodies.iter_mut().map(|b| &mut b.0).collect(), 1e11);
     }
 
-    bodies
+    Ok(())
 }
 
-fn circlev(elem: &Vec2) -> f64 {
-    ((G * 1e6 * SOLAR_MASS) / (elem.x().powi(2) + elem.y().powi(2)).sqrt()).sqrt()
+// Body colors give channels out of 255 and alpha out of 1.
+fn to_color(rgba: Rgba) -> Color {
+    let channel = |value: f32| value.clamp(0.0, 255.0).round() as u8;
+    [
+        channel(rgba.r),
+        channel(rgba.g),
+        channel(rgba.b),
+        channel(rgba.a * 255.0),
+    ]
 }
 
-fn rand_0_1() -> f64 {
-    thread_rng().gen_range(0.0, 1.0)
+fn create_batch(
+    bodies: &[ColorfulBody],
+    camera: &Camera,
+    w: f64,
+    h: f64,
+    selected: Option<usize>,
+) -> Batch {
+    let mut batch = Batch::new();
+
+    for (index, body) in bodies.iter().enumerate() {
+        let (x, y) = camera.to_screen(body.0.pos(), w, h);
+        let radius = if selected == Some(index) { 5.0 } else { 2.0 };
+        batch.add(Shape::Circle(
+            Point2::new(x as f32, y as f32),
+            ZDepth::ZERO,
+            radius,
+            32,
+            Stroke::NONE,
+            Fill::Solid(body.1),
+        ));
+    }
+
+    batch
+}
+
+fn create_bodies(size: usize, seed: u64, g: f64) -> Vec<ColorfulBody> {
+    assert!(size > 0);
+
+    Disk::new(
+        size - 1,
+        (size - 1) as f64 * 5.0 * SOLAR_MASS,
+        Profile::Exponential {
+            scale_length: 1.5e17,
+        },
+        g,
+    )
+    .with_central_mass(1e6 * SOLAR_MASS)
+    .with_mass_spread(0.99)
+    .with_seed(seed)
+    .generate()
+    .into_iter()
+    .enumerate()
+    .map(|(i, body)| {
+        // Heavier bodies are paler.
+        let color = if i == 0 {
+            Rgba::new(255.0, 0.0, 0.0, 1.0)
+        } else {
+            let shade = (body.mass() * 254.0 / (10.0 * SOLAR_MASS)) as f32;
+            Rgba::new(shade, shade, 255.0, 1.0)
+        };
+
+        ColorfulBody(body, color)
+    })
+    .collect()
 }
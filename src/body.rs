@@ -1,13 +1,15 @@
-pub const G: f64 = 6.67408e-11;
-
-use crate::vec2::Vec2;
+use crate::{force_law::ForceLaw, vec2::Vec2};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Body {
     pos: Vec2,
     velocity: Vec2,
     force: Vec2,
+    potential: f64,
     mass: f64,
+    charge: f64,
+    radius: f64,
+    test_particle: bool,
 }
 
 impl Body {
@@ -17,9 +19,30 @@ impl Body {
             velocity,
             mass,
             force: Vec2::zero(),
+            potential: 0.0,
+            charge: 0.0,
+            radius: 0.0,
+            test_particle: false,
         }
     }
 
+    pub fn with_charge(mut self, charge: f64) -> Body {
+        self.charge = charge;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: f64) -> Body {
+        self.radius = radius;
+        self
+    }
+
+    /// Test particles feel forces from other bodies but exert none. Their
+    /// mass only sets how strongly they couple to those forces.
+    pub fn with_test_particle(mut self, test_particle: bool) -> Body {
+        self.test_particle = test_particle;
+        self
+    }
+
     pub fn pos(&self) -> &Vec2 {
         &self.pos
     }
@@ -32,22 +55,63 @@ impl Body {
         &self.force
     }
 
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    pub fn charge(&self) -> f64 {
+        self.charge
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn potential(&self) -> f64 {
+        self.potential
+    }
+
+    pub fn is_test_particle(&self) -> bool {
+        self.test_particle
+    }
+
     pub fn update(&mut self, dt: f64) {
         self.velocity = self.velocity.clone() + self.force.clone() * dt / self.mass;
         self.pos = self.pos.clone() + self.velocity.clone() * dt;
     }
 
-    pub fn add_force(&mut self, other: &Body) {
-        let diff = self.pos.clone() - other.pos.clone();
-        let dist = self.pos.dist(&other.pos);
+    pub fn set_velocity(&mut self, velocity: Vec2) {
+        self.velocity = velocity;
+    }
+
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.velocity = self.velocity.clone() + impulse / self.mass;
+    }
+
+    /// Changes velocity by `acceleration * dt` regardless of mass.
+    pub fn accelerate(&mut self, acceleration: Vec2, dt: f64) {
+        self.velocity = self.velocity.clone() + acceleration * dt;
+    }
 
-        let force = (G * self.mass * other.mass) / dist.powi(2);
+    pub fn translate(&mut self, offset: Vec2) {
+        self.pos = self.pos.clone() + offset;
+    }
 
-        self.force = self.force.clone() + diff * force / dist;
+    pub fn add_force<L: ForceLaw>(&mut self, other: &Body, law: &L) {
+        let (force, potential) = law.interact(self, other);
+        self.add_interaction(force, potential);
+    }
+
+    /// Adds a force and potential energy computed outside of a force law,
+    /// such as from a mesh.
+    pub fn add_interaction(&mut self, force: Vec2, potential: f64) {
+        self.force = self.force.clone() + force;
+        self.potential += potential;
     }
 
     pub fn reset_force(&mut self) {
         self.force = Vec2::zero();
+        self.potential = 0.0;
     }
 
     pub fn add(&self, other: &Body) -> Body {
@@ -61,12 +125,32 @@ impl Body {
             Vec2::zero(),
             mass,
         )
+        .with_charge(self.charge + other.charge)
+        .with_test_particle(self.test_particle && other.test_particle)
+    }
+
+    pub fn overlaps(&self, other: &Body) -> bool {
+        self.pos.dist(&other.pos) < self.radius + other.radius
+    }
+
+    pub fn merge(&self, other: &Body) -> Body {
+        let mass = self.mass + other.mass;
+        let momentum = self.velocity.clone() * self.mass + other.velocity.clone() * other.mass;
+
+        // Merged bodies keep their combined area.
+        let radius = (self.radius.powi(2) + other.radius.powi(2)).sqrt();
+
+        Body::new(self.add(other).pos, momentum / mass, mass)
+            .with_charge(self.charge + other.charge)
+            .with_radius(radius)
+            .with_test_particle(self.test_particle && other.test_particle)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{force_law::Newtonian, units::G};
 
     #[test]
     fn updates_by_delta_time() {
@@ -79,22 +163,58 @@ mod tests {
         assert_eq!(body.pos(), &Vec2::new(10.1856, 9.18048));
     }
 
+    #[test]
+    fn applies_impulse() {
+        let mut body = Body::new(Vec2::new(10.0, 9.0), Vec2::unit(), 4.0);
+
+        body.apply_impulse(Vec2::new(2.0, -8.0));
+
+        assert_eq!(body.velocity(), &Vec2::new(1.5, -1.0));
+        assert_eq!(body.pos(), &Vec2::new(10.0, 9.0));
+    }
+
+    #[test]
+    fn translates() {
+        let mut body = Body::new(Vec2::new(10.0, 9.0), Vec2::unit(), 4.0);
+
+        body.translate(Vec2::new(-2.5, 1.0));
+
+        assert_eq!(body.pos(), &Vec2::new(7.5, 10.0));
+        assert_eq!(body.velocity(), &Vec2::unit());
+    }
+
     #[test]
     fn adds_force_from_another_body() {
         let mut first_body = Body::new(Vec2::new(10.0, 9.0), Vec2::unit(), 10.0);
         let second_body = Body::new(Vec2::new(7.0, 2.0), Vec2::unit(), 12.0);
         let third_body = Body::new(Vec2::new(3.0, 1.0), Vec2::unit(), 2.0);
 
-        first_body.add_force(&second_body);
+        first_body.add_force(&second_body, &Newtonian::new(G));
         assert_eq!(
             first_body.force(),
-            &Vec2::new(5.439411542609485e-11, 1.2691960266088797e-10)
+            &Vec2::new(-5.439411542609485e-11, -1.2691960266088797e-10)
         );
 
-        first_body.add_force(&third_body);
+        first_body.add_force(&third_body, &Newtonian::new(G));
         assert_eq!(
             first_body.force(),
-            &Vec2::new(6.217272150270214e-11, 1.358094381770106e-10)
+            &Vec2::new(-6.217272150270214e-11, -1.358094381770106e-10)
+        );
+    }
+
+    #[test]
+    fn adds_potential_from_another_body() {
+        let mut first_body = Body::new(Vec2::new(10.0, 9.0), Vec2::unit(), 10.0);
+        let second_body = Body::new(Vec2::new(7.0, 9.0), Vec2::unit(), 12.0);
+        let third_body = Body::new(Vec2::new(10.0, 5.0), Vec2::unit(), 2.0);
+
+        first_body.add_force(&second_body, &Newtonian::new(G));
+        assert_eq!(first_body.potential(), -G * 10.0 * 12.0 / 3.0);
+
+        first_body.add_force(&third_body, &Newtonian::new(G));
+        assert_eq!(
+            first_body.potential(),
+            -G * 10.0 * 12.0 / 3.0 - G * 10.0 * 2.0 / 4.0
         );
     }
 
@@ -102,10 +222,52 @@ mod tests {
     fn resets_its_force() {
         let mut body = Body::new(Vec2::unit(), Vec2::unit(), 10.0);
         body.force = Vec2::new(10.0, 8.0);
+        body.potential = -4.0;
 
         body.reset_force();
 
         assert_eq!(body.force(), &Vec2::zero());
+        assert_eq!(body.potential(), 0.0);
+    }
+
+    #[test]
+    fn returns_whether_overlaps_another_body() {
+        let body = Body::new(Vec2::zero(), Vec2::zero(), 1.0).with_radius(2.0);
+
+        assert!(body.overlaps(&Body::new(Vec2::new(2.5, 0.0), Vec2::zero(), 1.0).with_radius(1.0)));
+        assert!(!body.overlaps(&Body::new(Vec2::new(3.0, 0.0), Vec2::zero(), 1.0).with_radius(1.0)));
+        assert!(body.overlaps(&Body::new(Vec2::new(1.0, 0.0), Vec2::zero(), 1.0)));
+        assert!(
+            !Body::new(Vec2::zero(), Vec2::zero(), 1.0).overlaps(&Body::new(
+                Vec2::zero(),
+                Vec2::zero(),
+                1.0
+            ))
+        );
+    }
+
+    #[test]
+    fn merges_with_another_body() {
+        let first_body = Body::new(Vec2::new(5.0, 8.0), Vec2::new(2.0, -1.0), 10.0)
+            .with_charge(2.0)
+            .with_radius(3.0);
+        let second_body = Body::new(Vec2::new(9.0, 1.0), Vec2::new(-2.0, 4.0), 15.0)
+            .with_charge(-0.5)
+            .with_radius(4.0);
+
+        assert_eq!(
+            first_body.merge(&second_body),
+            Body::new(Vec2::new(7.4, 3.8), Vec2::new(-0.4, 2.0), 25.0)
+                .with_charge(1.5)
+                .with_radius(5.0),
+        );
+
+        // Only two test particles merge into a test particle.
+        let tracer = first_body.clone().with_test_particle(true);
+        assert!(!tracer.merge(&second_body).is_test_particle());
+        assert!(tracer
+            .merge(&second_body.with_test_particle(true))
+            .is_test_particle());
     }
 
     #[test]
@@ -0,0 +1,161 @@
+use super::broad_phase::overlapping_pairs;
+use crate::{body::Body, vec2::Vec2};
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Bounce {
+    restitution: f64,
+    friction: f64,
+}
+
+impl Bounce {
+    /// `restitution` is 1 for elastic and 0 for perfectly inelastic collisions,
+    /// `friction` is the Coulomb coefficient limiting the tangential impulse.
+    pub fn new(restitution: f64, friction: f64) -> Bounce {
+        Bounce {
+            restitution,
+            friction,
+        }
+    }
+
+    pub fn elastic() -> Bounce {
+        Bounce::new(1.0, 0.0)
+    }
+
+    pub fn restitution(&self) -> f64 {
+        self.restitution
+    }
+
+    pub fn friction(&self) -> f64 {
+        self.friction
+    }
+
+    /// Resolves every overlapping pair and returns how many there were.
+    pub fn resolve(&self, bodies: &mut [&mut Body]) -> usize {
+        let pairs = overlapping_pairs(bodies);
+
+        for &(i, j) in &pairs {
+            let (head, tail) = bodies.split_at_mut(j);
+            self.resolve_pair(head[i], tail[0]);
+        }
+
+        pairs.len()
+    }
+
+    pub fn resolve_pair(&self, first: &mut Body, second: &mut Body) {
+        let diff = second.pos().clone() - first.pos().clone();
+        let dist = diff.length();
+        let normal = if dist > 0.0 {
+            diff / dist
+        } else {
+            Vec2::new(1.0, 0.0)
+        };
+
+        let inverse_mass_sum = 1.0 / first.mass() + 1.0 / second.mass();
+
+        // Push the bodies apart so they only touch, heavier ones moving less.
+        let penetration = first.radius() + second.radius() - dist;
+        if penetration > 0.0 {
+            let correction = normal.clone() * (penetration / inverse_mass_sum);
+            first.translate(correction.clone() * (-1.0 / first.mass()));
+            second.translate(correction * (1.0 / second.mass()));
+        }
+
+        let relative_velocity = second.velocity().clone() - first.velocity().clone();
+        let normal_velocity = relative_velocity.dot(&normal);
+        if normal_velocity >= 0.0 {
+            return;
+        }
+
+        let normal_impulse = -(1.0 + self.restitution) * normal_velocity / inverse_mass_sum;
+
+        let tangential_velocity = relative_velocity - normal.clone() * normal_velocity;
+        let tangential_speed = tangential_velocity.length();
+        let tangential_impulse = if tangential_speed > 0.0 {
+            let impulse = (tangential_speed / inverse_mass_sum).min(self.friction * normal_impulse);
+            tangential_velocity * (-impulse / tangential_speed)
+        } else {
+            Vec2::zero()
+        };
+
+        let impulse = normal * normal_impulse + tangential_impulse;
+        first.apply_impulse(impulse.clone() * -1.0);
+        second.apply_impulse(impulse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn momentum(bodies: &[&Body]) -> Vec2 {
+        bodies
+            .iter()
+            .fold(Vec2::zero(), |p, b| p + b.velocity().clone() * b.mass())
+    }
+
+    #[test]
+    fn swaps_velocities_of_equal_masses_in_elastic_collision() {
+        let mut first = Body::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), 2.0).with_radius(1.0);
+        let mut second = Body::new(Vec2::new(2.0, 0.0), Vec2::new(-3.0, 0.0), 2.0).with_radius(1.0);
+
+        Bounce::elastic().resolve_pair(&mut first, &mut second);
+
+        assert_eq!(first.velocity(), &Vec2::new(-3.0, 0.0));
+        assert_eq!(second.velocity(), &Vec2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn moves_together_in_perfectly_inelastic_collision() {
+        let mut first = Body::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0), 3.0).with_radius(1.0);
+        let mut second = Body::new(Vec2::new(1.5, 0.0), Vec2::new(0.0, 0.0), 1.0).with_radius(1.0);
+
+        Bounce::new(0.0, 0.0).resolve_pair(&mut first, &mut second);
+
+        assert_eq!(first.velocity(), &Vec2::new(3.0, 0.0));
+        assert_eq!(second.velocity(), &Vec2::new(3.0, 0.0));
+        assert_eq!(first.pos(), &Vec2::new(-0.125, 0.0));
+        assert_eq!(second.pos(), &Vec2::new(1.875, 0.0));
+    }
+
+    #[test]
+    fn conserves_momentum_and_loses_energy_with_restitution_and_friction() {
+        let mut first = Body::new(Vec2::new(0.0, 0.0), Vec2::new(2.0, 1.0), 3.0).with_radius(1.0);
+        let mut second =
+            Body::new(Vec2::new(1.0, 1.0), Vec2::new(-1.0, -2.0), 5.0).with_radius(1.0);
+        let energy = |bodies: &[&Body]| {
+            bodies
+                .iter()
+                .map(|b| 0.5 * b.mass() * b.velocity().dot(b.velocity()))
+                .sum::<f64>()
+        };
+        let initial_momentum = momentum(&[&first, &second]);
+        let initial_energy = energy(&[&first, &second]);
+
+        Bounce::new(0.5, 0.3).resolve_pair(&mut first, &mut second);
+
+        let final_momentum = momentum(&[&first, &second]);
+        assert!((final_momentum.x() - initial_momentum.x()).abs() < 1e-12);
+        assert!((final_momentum.y() - initial_momentum.y()).abs() < 1e-12);
+        assert!(energy(&[&first, &second]) < initial_energy);
+
+        let relative_velocity = second.velocity().clone() - first.velocity().clone();
+        let normal = (second.pos().clone() - first.pos().clone()) / first.pos().dist(second.pos());
+        assert!(relative_velocity.dot(&normal) > 0.0);
+    }
+
+    #[test]
+    fn only_separates_bodies_already_moving_apart() {
+        let mut first = Body::new(Vec2::new(0.0, 0.0), Vec2::new(-1.0, 0.0), 1.0).with_radius(2.0);
+        let mut second = Body::new(Vec2::new(3.0, 0.0), Vec2::new(1.0, 0.0), 1.0).with_radius(2.0);
+        let mut third = Body::new(Vec2::new(10.0, 0.0), Vec2::zero(), 1.0).with_radius(2.0);
+
+        let contacts = Bounce::elastic().resolve(&mut [&mut first, &mut second, &mut third]);
+
+        assert_eq!(contacts, 1);
+        assert_eq!(first.velocity(), &Vec2::new(-1.0, 0.0));
+        assert_eq!(second.velocity(), &Vec2::new(1.0, 0.0));
+        assert_eq!(first.pos(), &Vec2::new(-0.5, 0.0));
+        assert_eq!(second.pos(), &Vec2::new(3.5, 0.0));
+        assert_eq!(third.velocity(), &Vec2::zero());
+    }
+}
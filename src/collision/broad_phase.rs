@@ -0,0 +1,182 @@
+use crate::{body::Body, simulation::barnes_hut::quad::Quad, vec2::Vec2};
+use std::borrow::Borrow;
+
+const CAPACITY: usize = 8;
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug)]
+pub struct QuadIndex {
+    quad: Quad,
+    entries: Vec<(usize, Vec2)>,
+    children: Vec<QuadIndex>,
+}
+
+impl QuadIndex {
+    pub fn new(quad: Quad) -> QuadIndex {
+        QuadIndex {
+            quad,
+            entries: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn from_bodies<B: Borrow<Body>>(bodies: &[B]) -> QuadIndex {
+        let mut index = QuadIndex::new(bounding_quad(bodies));
+
+        for (i, body) in bodies.iter().enumerate() {
+            index.insert(i, body.borrow().pos().clone());
+        }
+
+        index
+    }
+
+    pub fn insert(&mut self, index: usize, pos: Vec2) {
+        self.insert_at_depth(index, pos, 0);
+    }
+
+    pub fn query(&self, region: &Quad, found: &mut Vec<usize>) {
+        if !self.quad.intersects(region) {
+            return;
+        }
+
+        found.extend(
+            self.entries
+                .iter()
+                .filter(|(_, pos)| region.contains(pos))
+                .map(|(index, _)| *index),
+        );
+
+        for child in &self.children {
+            child.query(region, found);
+        }
+    }
+
+    fn insert_at_depth(&mut self, index: usize, pos: Vec2, depth: usize) {
+        if self.children.is_empty() {
+            self.entries.push((index, pos));
+
+            if self.entries.len() > CAPACITY && depth < MAX_DEPTH {
+                self.children = vec![
+                    QuadIndex::new(self.quad.northwest()),
+                    QuadIndex::new(self.quad.northeast()),
+                    QuadIndex::new(self.quad.southwest()),
+                    QuadIndex::new(self.quad.southeast()),
+                ];
+
+                for (index, pos) in std::mem::take(&mut self.entries) {
+                    self.insert_into_child(index, pos, depth);
+                }
+            }
+
+            return;
+        }
+
+        self.insert_into_child(index, pos, depth);
+    }
+
+    fn insert_into_child(&mut self, index: usize, pos: Vec2, depth: usize) {
+        let child = self
+            .children
+            .iter()
+            .position(|child| child.quad.contains(&pos))
+            .unwrap_or(self.children.len() - 1);
+
+        self.children[child].insert_at_depth(index, pos, depth + 1);
+    }
+}
+
+/// Pairs of indices `(i, j)` with `i < j` whose bodies overlap.
+pub fn overlapping_pairs<B: Borrow<Body>>(bodies: &[B]) -> Vec<(usize, usize)> {
+    let max_radius = bodies
+        .iter()
+        .map(|b| b.borrow().radius())
+        .fold(0.0, f64::max);
+    if max_radius <= 0.0 {
+        return Vec::new();
+    }
+
+    let index = QuadIndex::from_bodies(bodies);
+    let mut pairs = Vec::new();
+    let mut candidates = Vec::new();
+
+    for (i, body) in bodies.iter().map(Borrow::borrow).enumerate() {
+        candidates.clear();
+        index.query(
+            &Quad::new(body.pos().clone(), 2.0 * (body.radius() + max_radius)),
+            &mut candidates,
+        );
+        candidates.sort_unstable();
+
+        for &j in candidates.iter().filter(|&&j| j > i) {
+            if body.overlaps(bodies[j].borrow()) {
+                pairs.push((i, j));
+            }
+        }
+    }
+
+    pairs
+}
+
+fn bounding_quad<B: Borrow<Body>>(bodies: &[B]) -> Quad {
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+    for body in bodies.iter().map(Borrow::borrow) {
+        min_x = min_x.min(body.pos().x());
+        min_y = min_y.min(body.pos().y());
+        max_x = max_x.max(body.pos().x());
+        max_y = max_y.max(body.pos().y());
+    }
+
+    if bodies.is_empty() {
+        return Quad::new(Vec2::zero(), 1.0);
+    }
+
+    Quad::new(
+        Vec2::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0),
+        (max_x - min_x).max(max_y - min_y).max(f64::MIN_POSITIVE),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn finds_same_pairs_as_checking_every_pair() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let bodies: Vec<Body> = (0..300)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-100.0, 100.0), rng.gen_range(-100.0, 100.0)),
+                    Vec2::zero(),
+                    1.0,
+                )
+                .with_radius(rng.gen_range(0.0, 4.0))
+            })
+            .collect();
+
+        let mut expected_pairs = Vec::new();
+        for i in 0..bodies.len() {
+            for j in (i + 1)..bodies.len() {
+                if bodies[i].overlaps(&bodies[j]) {
+                    expected_pairs.push((i, j));
+                }
+            }
+        }
+
+        assert!(!expected_pairs.is_empty());
+        assert_eq!(overlapping_pairs(&bodies), expected_pairs);
+    }
+
+    #[test]
+    fn finds_no_pairs_for_point_bodies() {
+        let bodies = vec![
+            Body::new(Vec2::zero(), Vec2::zero(), 1.0),
+            Body::new(Vec2::zero(), Vec2::zero(), 1.0),
+        ];
+
+        assert_eq!(overlapping_pairs(&bodies), vec![]);
+    }
+}
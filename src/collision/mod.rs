@@ -0,0 +1,119 @@
+pub mod bounce;
+pub mod broad_phase;
+
+use crate::body::Body;
+use broad_phase::overlapping_pairs;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Merge {
+    target: usize,
+    absorbed: usize,
+    body: Body,
+}
+
+impl Merge {
+    /// Index, before the merge, of the body that was kept.
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Index, before the merge, of the body that was removed.
+    pub fn absorbed(&self) -> usize {
+        self.absorbed
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+}
+
+pub fn merge_collisions(bodies: &mut Vec<Body>) -> Vec<Merge> {
+    let mut merges = Vec::new();
+    let mut absorbed = vec![false; bodies.len()];
+
+    for (i, j) in overlapping_pairs(bodies) {
+        if absorbed[i] || absorbed[j] {
+            continue;
+        }
+
+        bodies[i] = bodies[i].merge(&bodies[j]);
+        absorbed[j] = true;
+
+        merges.push(Merge {
+            target: i,
+            absorbed: j,
+            body: bodies[i].clone(),
+        });
+    }
+
+    let mut absorbed = absorbed.into_iter();
+    bodies.retain(|_| !absorbed.next().unwrap());
+
+    merges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec2::Vec2;
+
+    #[test]
+    fn merges_overlapping_bodies() {
+        let mut bodies = vec![
+            Body::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), 2.0).with_radius(1.0),
+            Body::new(Vec2::new(10.0, 0.0), Vec2::zero(), 1.0).with_radius(1.0),
+            Body::new(Vec2::new(1.5, 0.0), Vec2::new(-1.0, 2.0), 6.0).with_radius(1.0),
+            Body::new(Vec2::new(-50.0, 0.0), Vec2::zero(), 1.0),
+        ];
+        let momentum = |bodies: &Vec<Body>| {
+            bodies
+                .iter()
+                .fold(Vec2::zero(), |p, b| p + b.velocity().clone() * b.mass())
+        };
+        let initial_momentum = momentum(&bodies);
+
+        let merges = merge_collisions(&mut bodies);
+
+        let merged_body = Body::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), 2.0)
+            .with_radius(1.0)
+            .merge(&Body::new(Vec2::new(1.5, 0.0), Vec2::new(-1.0, 2.0), 6.0).with_radius(1.0));
+        assert_eq!(
+            merges,
+            vec![Merge {
+                target: 0,
+                absorbed: 2,
+                body: merged_body.clone(),
+            }]
+        );
+        assert_eq!(
+            bodies,
+            vec![
+                merged_body,
+                Body::new(Vec2::new(10.0, 0.0), Vec2::zero(), 1.0).with_radius(1.0),
+                Body::new(Vec2::new(-50.0, 0.0), Vec2::zero(), 1.0),
+            ]
+        );
+        assert_eq!(momentum(&bodies), initial_momentum);
+        assert_eq!(bodies.iter().map(|b| b.mass()).sum::<f64>(), 10.0);
+    }
+
+    #[test]
+    fn does_not_merge_absorbed_bodies_again() {
+        let mut bodies = vec![
+            Body::new(Vec2::new(0.0, 0.0), Vec2::zero(), 1.0).with_radius(1.0),
+            Body::new(Vec2::new(1.0, 0.0), Vec2::zero(), 1.0).with_radius(1.0),
+            Body::new(Vec2::new(2.0, 0.0), Vec2::zero(), 1.0).with_radius(1.0),
+        ];
+
+        let merges = merge_collisions(&mut bodies);
+
+        assert_eq!(
+            merges
+                .iter()
+                .map(|m| (m.target(), m.absorbed()))
+                .collect::<Vec<_>>(),
+            vec![(0, 1)]
+        );
+        assert_eq!(bodies.len(), 2);
+    }
+}
@@ -0,0 +1,145 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    pub fn zero() -> Complex {
+        Complex::new(0.0, 0.0)
+    }
+
+    pub fn from_polar(radius: f64, angle: f64) -> Complex {
+        let (sin, cos) = angle.sin_cos();
+
+        Complex::new(radius * cos, radius * sin)
+    }
+
+    pub fn re(&self) -> f64 {
+        self.re
+    }
+
+    pub fn im(&self) -> f64 {
+        self.im
+    }
+
+    pub fn conj(&self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    /// Principal branch of the natural logarithm.
+    pub fn ln(&self) -> Complex {
+        Complex::new(self.norm().ln(), self.arg())
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: f64) -> Complex {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Complex) -> Complex {
+        let norm_squared = rhs.re.powi(2) + rhs.im.powi(2);
+
+        (self * rhs.conj()) * (1.0 / norm_squared)
+    }
+}
+
+impl Div<f64> for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: f64) -> Complex {
+        Complex::new(self.re / rhs, self.im / rhs)
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplies_and_divides() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -1.0);
+
+        assert_eq!(a * b, Complex::new(5.0, 5.0));
+        assert_eq!((a * b) / b, a);
+        assert_eq!(a.conj(), Complex::new(1.0, -2.0));
+        assert_eq!(Complex::new(3.0, 4.0).norm(), 5.0);
+    }
+
+    #[test]
+    fn creates_from_polar_form() {
+        let z = Complex::from_polar(2.0, std::f64::consts::FRAC_PI_2);
+
+        assert!(z.re().abs() < 1e-15);
+        assert_eq!(z.im(), 2.0);
+        assert_eq!(z.arg(), std::f64::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn takes_logarithm() {
+        let z = Complex::new(-1.0, 0.0).ln();
+
+        assert_eq!(z, Complex::new(0.0, std::f64::consts::PI));
+        assert_eq!(
+            Complex::new(std::f64::consts::E, 0.0).ln(),
+            Complex::new(1.0, 0.0)
+        );
+    }
+}
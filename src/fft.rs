@@ -0,0 +1,125 @@
+use crate::complex::Complex;
+use std::f64::consts::PI;
+
+/// In-place radix-2 transform of `data`, whose length must be a power of
+/// two. The inverse transform is scaled by `1 / len`, so a forward and an
+/// inverse transform give back the input.
+pub fn fft(data: &mut [Complex], inverse: bool) {
+    let len = data.len();
+    assert!(len.is_power_of_two(), "length must be a power of two");
+
+    let mut j = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut width = 2;
+    while width <= len {
+        let step = Complex::from_polar(1.0, sign * 2.0 * PI / width as f64);
+
+        for start in (0..len).step_by(width) {
+            let mut twiddle = Complex::new(1.0, 0.0);
+            for k in 0..width / 2 {
+                let even = data[start + k];
+                let odd = data[start + k + width / 2] * twiddle;
+
+                data[start + k] = even + odd;
+                data[start + k + width / 2] = even - odd;
+                twiddle = twiddle * step;
+            }
+        }
+
+        width *= 2;
+    }
+
+    if inverse {
+        for value in data.iter_mut() {
+            *value = *value / len as f64;
+        }
+    }
+}
+
+/// Transform of a `size` by `size` grid stored row by row.
+pub fn fft2(data: &mut [Complex], size: usize, inverse: bool) {
+    assert_eq!(data.len(), size * size);
+
+    for row in data.chunks_mut(size) {
+        fft(row, inverse);
+    }
+
+    let mut column = vec![Complex::zero(); size];
+    for i in 0..size {
+        for j in 0..size {
+            column[j] = data[j * size + i];
+        }
+        fft(&mut column, inverse);
+        for j in 0..size {
+            data[j * size + i] = column[j];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dft(data: &[Complex]) -> Vec<Complex> {
+        let len = data.len();
+
+        (0..len)
+            .map(|k| {
+                data.iter()
+                    .enumerate()
+                    .fold(Complex::zero(), |sum, (n, &x)| {
+                        sum + x * Complex::from_polar(1.0, -2.0 * PI * (k * n) as f64 / len as f64)
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_discrete_fourier_transform() {
+        let data: Vec<Complex> = (0..16)
+            .map(|i| Complex::new((i as f64).sin(), (i * i % 7) as f64))
+            .collect();
+
+        let mut transformed = data.clone();
+        fft(&mut transformed, false);
+
+        for (value, expected) in transformed.iter().zip(dft(&data)) {
+            assert!((*value - expected).norm() < 1e-12);
+        }
+
+        fft(&mut transformed, true);
+        for (value, expected) in transformed.iter().zip(&data) {
+            assert!((*value - *expected).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn transforms_grids() {
+        let size = 8;
+        let mut data = vec![Complex::zero(); size * size];
+        data[3 * size + 2] = Complex::new(1.0, 0.0);
+
+        fft2(&mut data, size, false);
+
+        // A single impulse has a flat spectrum with a linear phase.
+        for j in 0..size {
+            for i in 0..size {
+                let phase = -2.0 * PI * (2 * i + 3 * j) as f64 / size as f64;
+                assert!((data[j * size + i] - Complex::from_polar(1.0, phase)).norm() < 1e-12);
+            }
+        }
+    }
+}
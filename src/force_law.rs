@@ -0,0 +1,251 @@
+use crate::{body::Body, vec2::Vec2};
+
+pub trait ForceLaw {
+    /// Force exerted on `body` by `other` and the potential energy of the
+    /// pair.
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64);
+}
+
+/// Laws sourced by mass alone, so that far away a group of bodies can be
+/// replaced by one body carrying their total mass at their center of mass,
+/// the leading term of its multipole expansion. Signed charges have no such
+/// center; a neutral group acts through its dipole moment.
+pub trait MultipoleExpansion: ForceLaw {}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Newtonian {
+    g: f64,
+}
+
+impl Newtonian {
+    pub fn new(g: f64) -> Newtonian {
+        Newtonian { g }
+    }
+}
+
+impl ForceLaw for Newtonian {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        let diff = other.pos().clone() - body.pos().clone();
+        let dist = body.pos().dist(other.pos());
+
+        let coupling = self.g * body.mass() * other.mass();
+        let force = coupling / dist.powi(2);
+
+        (diff * force / dist, -coupling / dist)
+    }
+}
+
+impl MultipoleExpansion for Newtonian {}
+
+/// Electrostatic force between signed charges; like charges repel.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Coulomb {
+    k: f64,
+}
+
+impl Coulomb {
+    pub fn new(k: f64) -> Coulomb {
+        Coulomb { k }
+    }
+}
+
+impl ForceLaw for Coulomb {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        let (direction, dist) = direction(body, other);
+        let coupling = self.k * body.charge() * other.charge();
+
+        (direction * (coupling / dist.powi(2)), coupling / dist)
+    }
+}
+
+/// Coulomb force screened beyond `screening_length`, as in a plasma.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Yukawa {
+    k: f64,
+    screening_length: f64,
+}
+
+impl Yukawa {
+    pub fn new(k: f64, screening_length: f64) -> Yukawa {
+        Yukawa {
+            k,
+            screening_length,
+        }
+    }
+}
+
+impl ForceLaw for Yukawa {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        let (direction, dist) = direction(body, other);
+        let potential =
+            self.k * body.charge() * other.charge() * (-dist / self.screening_length).exp() / dist;
+
+        (
+            direction * (potential * (1.0 / dist + 1.0 / self.screening_length)),
+            potential,
+        )
+    }
+}
+
+/// Pair potential `4 epsilon ((sigma / r)^12 - (sigma / r)^6)`, independent
+/// of mass and charge.
+#[derive(PartialEq, Debug, Clone)]
+pub struct LennardJones {
+    epsilon: f64,
+    sigma: f64,
+}
+
+impl LennardJones {
+    pub fn new(epsilon: f64, sigma: f64) -> LennardJones {
+        LennardJones { epsilon, sigma }
+    }
+}
+
+impl ForceLaw for LennardJones {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        let (direction, dist) = direction(body, other);
+        let attraction = (self.sigma / dist).powi(6);
+        let repulsion = attraction.powi(2);
+
+        (
+            direction * (24.0 * self.epsilon * (2.0 * repulsion - attraction) / dist),
+            4.0 * self.epsilon * (repulsion - attraction),
+        )
+    }
+}
+
+/// Attraction between masses falling off as `r^-exponent`; an exponent of 2
+/// is Newtonian gravity.
+#[derive(PartialEq, Debug, Clone)]
+pub struct PowerLaw {
+    g: f64,
+    exponent: f64,
+}
+
+impl PowerLaw {
+    pub fn new(g: f64, exponent: f64) -> PowerLaw {
+        PowerLaw { g, exponent }
+    }
+}
+
+impl ForceLaw for PowerLaw {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        let (direction, dist) = direction(body, other);
+        let coupling = self.g * body.mass() * other.mass();
+
+        let potential = if self.exponent == 1.0 {
+            coupling * dist.ln()
+        } else {
+            -coupling / ((self.exponent - 1.0) * dist.powf(self.exponent - 1.0))
+        };
+
+        (
+            direction * -(coupling / dist.powf(self.exponent)),
+            potential,
+        )
+    }
+}
+
+impl MultipoleExpansion for PowerLaw {}
+
+// Unit vector from `other` to `body` and the distance between them.
+fn direction(body: &Body, other: &Body) -> (Vec2, f64) {
+    let dist = body.pos().dist(other.pos());
+
+    ((body.pos().clone() - other.pos().clone()) / dist, dist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bodies() -> (Body, Body) {
+        (
+            Body::new(Vec2::new(1.0, 1.0), Vec2::zero(), 2.0).with_charge(-3.0),
+            Body::new(Vec2::new(4.0, 5.0), Vec2::zero(), 4.0).with_charge(-1.5),
+        )
+    }
+
+    // Compares the force with the negative gradient of the potential.
+    fn assert_conservative<L: ForceLaw>(law: &L) {
+        let (body, other) = bodies();
+        let (force, _) = law.interact(&body, &other);
+
+        let h = 1e-6;
+        let potential_at = |offset: Vec2| {
+            let mut moved = body.clone();
+            moved.translate(offset);
+            law.interact(&moved, &other).1
+        };
+        let gradient = Vec2::new(
+            (potential_at(Vec2::new(h, 0.0)) - potential_at(Vec2::new(-h, 0.0))) / (2.0 * h),
+            (potential_at(Vec2::new(0.0, h)) - potential_at(Vec2::new(0.0, -h))) / (2.0 * h),
+        );
+
+        assert!((force.clone() + gradient).length() < 1e-6 * force.length());
+    }
+
+    #[test]
+    fn derives_forces_from_potentials() {
+        assert_conservative(&Newtonian::new(1.5));
+        assert_conservative(&Coulomb::new(2.0));
+        assert_conservative(&Yukawa::new(2.0, 3.0));
+        assert_conservative(&LennardJones::new(0.5, 4.0));
+        assert_conservative(&PowerLaw::new(1.5, 3.5));
+        assert_conservative(&PowerLaw::new(1.5, 1.0));
+    }
+
+    #[test]
+    fn attracts_masses_with_newtonian_gravity() {
+        let (body, other) = bodies();
+
+        let (force, potential) = Newtonian::new(1.5).interact(&body, &other);
+
+        assert_eq!(potential, -1.5 * 2.0 * 4.0 / 5.0);
+        assert!((force - Vec2::new(3.0, 4.0) * (1.5 * 2.0 * 4.0 / 125.0)).length() < 1e-15);
+    }
+
+    #[test]
+    fn repels_like_charges() {
+        let (body, other) = bodies();
+
+        let (force, potential) = Coulomb::new(2.0).interact(&body, &other);
+
+        assert_eq!(potential, 2.0 * 4.5 / 5.0);
+        assert!((force - Vec2::new(-3.0, -4.0) * (2.0 * 4.5 / 125.0)).length() < 1e-15);
+    }
+
+    #[test]
+    fn matches_newtonian_gravity_with_exponent_two() {
+        let (body, other) = bodies();
+
+        assert_eq!(
+            PowerLaw::new(1.5, 2.0).interact(&body, &other),
+            Newtonian::new(1.5).interact(&body, &other)
+        );
+    }
+
+    #[test]
+    fn screens_coulomb_force() {
+        let (body, other) = bodies();
+
+        let (coulomb, _) = Coulomb::new(2.0).interact(&body, &other);
+        let (screened, _) = Yukawa::new(2.0, 1.0).interact(&body, &other);
+        let (unscreened, _) = Yukawa::new(2.0, 1e12).interact(&body, &other);
+
+        assert!(screened.length() < 0.1 * coulomb.length());
+        assert!((unscreened - coulomb.clone()).length() < 1e-9 * coulomb.length());
+    }
+
+    #[test]
+    fn has_lennard_jones_minimum() {
+        let law = LennardJones::new(0.5, 1.0);
+        let body = Body::new(Vec2::zero(), Vec2::zero(), 1.0);
+        let other = Body::new(Vec2::new(2f64.powf(1.0 / 6.0), 0.0), Vec2::zero(), 1.0);
+
+        let (force, potential) = law.interact(&body, &other);
+
+        assert!(force.length() < 1e-12);
+        assert!((potential + 0.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{
+    body::Body,
+    ic::{to_center_of_mass_frame, Generator},
+    vec2::Vec2,
+};
+use rand::Rng;
+use std::f64::consts::PI;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Profile {
+    /// Surface density proportional to `exp(-R / scale_length)`.
+    Exponential { scale_length: f64 },
+    /// Surface density proportional to `1 / R`, truncated at `radius`.
+    Mestel { radius: f64 },
+}
+
+impl Profile {
+    fn sample_radius<R: Rng>(&self, rng: &mut R) -> f64 {
+        match *self {
+            // R Σ(R) is a gamma distribution with shape 2.
+            Profile::Exponential { scale_length } => {
+                let u: f64 = rng.gen_range(0.0, 1.0);
+                let v: f64 = rng.gen_range(0.0, 1.0);
+                -scale_length * ((1.0 - u) * (1.0 - v)).ln()
+            }
+            Profile::Mestel { radius } => rng.gen_range(0.0, radius),
+        }
+    }
+
+    fn enclosed_mass_fraction(&self, r: f64) -> f64 {
+        match *self {
+            Profile::Exponential { scale_length } => {
+                let x = r / scale_length;
+                1.0 - (1.0 + x) * (-x).exp()
+            }
+            Profile::Mestel { radius } => (r / radius).min(1.0),
+        }
+    }
+}
+
+/// Logarithmic halo whose circular velocity rises to `velocity` outside
+/// `core_radius`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Halo {
+    velocity: f64,
+    core_radius: f64,
+}
+
+impl Halo {
+    pub fn new(velocity: f64, core_radius: f64) -> Halo {
+        Halo {
+            velocity,
+            core_radius,
+        }
+    }
+
+    fn circular_velocity_squared(&self, r: f64) -> f64 {
+        self.velocity.powi(2) * r.powi(2) / (r.powi(2) + self.core_radius.powi(2))
+    }
+}
+
+/// Rotating disk of bodies, optionally around a central body. Bodies have
+/// equal masses unless `with_mass_spread` is given.
+///
+/// Circular velocities treat the disk mass inside a radius as if it were
+/// spherically distributed.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Disk {
+    size: usize,
+    mass: f64,
+    profile: Profile,
+    g: f64,
+    central_mass: f64,
+    halo: Option<Halo>,
+    velocity_dispersion: f64,
+    counter_rotating_fraction: f64,
+    mass_spread: f64,
+    seed: u64,
+}
+
+impl Disk {
+    pub fn new(size: usize, mass: f64, profile: Profile, g: f64) -> Disk {
+        Disk {
+            size,
+            mass,
+            profile,
+            g,
+            central_mass: 0.0,
+            halo: None,
+            velocity_dispersion: 0.0,
+            counter_rotating_fraction: 0.0,
+            mass_spread: 0.0,
+            seed: 0,
+        }
+    }
+
+    /// Adds a body of `mass` at the center, placed before the disk bodies.
+    pub fn with_central_mass(mut self, mass: f64) -> Disk {
+        self.central_mass = mass;
+        self
+    }
+
+    pub fn with_halo(mut self, halo: Halo) -> Disk {
+        self.halo = Some(halo);
+        self
+    }
+
+    pub fn with_velocity_dispersion(mut self, velocity_dispersion: f64) -> Disk {
+        self.velocity_dispersion = velocity_dispersion;
+        self
+    }
+
+    pub fn with_counter_rotating_fraction(mut self, fraction: f64) -> Disk {
+        self.counter_rotating_fraction = fraction;
+        self
+    }
+
+    /// Draws each body's mass uniformly within `mass_spread` times the mean
+    /// on either side of it, keeping the total. Spreads below 1 keep every
+    /// mass positive.
+    pub fn with_mass_spread(mut self, mass_spread: f64) -> Disk {
+        self.mass_spread = mass_spread;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Disk {
+        self.seed = seed;
+        self
+    }
+
+    pub fn central_mass(&self) -> f64 {
+        self.central_mass
+    }
+
+    pub fn circular_velocity(&self, r: f64) -> f64 {
+        if r == 0.0 {
+            return 0.0;
+        }
+
+        let enclosed_mass = self.central_mass + self.mass * self.profile.enclosed_mass_fraction(r);
+        let halo = self
+            .halo
+            .as_ref()
+            .map_or(0.0, |h| h.circular_velocity_squared(r));
+
+        (self.g * enclosed_mass / r + halo).sqrt()
+    }
+}
+
+impl Generator for Disk {
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn generate_with_rng<R: Rng>(&self, rng: &mut R) -> Vec<Body> {
+        let mut bodies = Vec::with_capacity(self.size + 1);
+
+        if self.central_mass > 0.0 {
+            bodies.push(Body::new(Vec2::zero(), Vec2::zero(), self.central_mass));
+        }
+
+        let mut masses = vec![self.mass / self.size as f64; self.size];
+        if self.mass_spread > 0.0 {
+            for mass in masses.iter_mut() {
+                *mass *= rng.gen_range(1.0 - self.mass_spread, 1.0 + self.mass_spread);
+            }
+            let scale = self.mass / masses.iter().sum::<f64>();
+            for mass in masses.iter_mut() {
+                *mass *= scale;
+            }
+        }
+
+        for mass in masses {
+            let r = self.profile.sample_radius(rng);
+            let angle = rng.gen_range(0.0, 2.0 * PI);
+            let (radial, tangential) = (
+                Vec2::new(angle.cos(), angle.sin()),
+                Vec2::new(-angle.sin(), angle.cos()),
+            );
+
+            let direction = if rng.gen_range(0.0, 1.0) < self.counter_rotating_fraction {
+                -1.0
+            } else {
+                1.0
+            };
+
+            let velocity = tangential.clone()
+                * (direction * self.circular_velocity(r)
+                    + self.velocity_dispersion * gaussian(rng))
+                + radial.clone() * (self.velocity_dispersion * gaussian(rng));
+
+            bodies.push(Body::new(radial * r, velocity, mass));
+        }
+
+        to_center_of_mass_frame(&mut bodies);
+
+        bodies
+    }
+}
+
+// Standard normal sample using the Box-Muller transform.
+fn gaussian<R: Rng>(rng: &mut R) -> f64 {
+    let u: f64 = rng.gen_range(f64::MIN_POSITIVE, 1.0);
+    let v: f64 = rng.gen_range(0.0, 2.0 * PI);
+
+    (-2.0 * u.ln()).sqrt() * v.cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::G;
+
+    fn angular_momentum(body: &Body) -> f64 {
+        body.mass() * (body.pos().x() * body.velocity().y() - body.pos().y() * body.velocity().x())
+    }
+
+    #[test]
+    fn generates_same_bodies_for_same_seed() {
+        let disk = Disk::new(100, 1e30, Profile::Exponential { scale_length: 1e12 }, G)
+            .with_velocity_dispersion(1e3)
+            .with_seed(5);
+
+        assert_eq!(disk.generate(), disk.generate());
+        assert_ne!(disk.generate(), disk.clone().with_seed(6).generate());
+    }
+
+    #[test]
+    fn generates_corotating_circular_orbits_around_central_mass() {
+        let disk = Disk::new(1000, 1e20, Profile::Exponential { scale_length: 1e12 }, G)
+            .with_central_mass(1e30);
+        let bodies = disk.generate();
+
+        assert_eq!(bodies.len(), 1001);
+        assert!(bodies[0].pos().length() < 1e3);
+
+        for body in &bodies[1..] {
+            let expected_speed = disk.circular_velocity(body.pos().length());
+            assert!((body.velocity().length() - expected_speed).abs() / expected_speed < 1e-6);
+            assert!(angular_momentum(body) > 0.0);
+        }
+    }
+
+    #[test]
+    fn generates_requested_counter_rotating_fraction() {
+        let bodies = Disk::new(2000, 1e20, Profile::Mestel { radius: 1e12 }, G)
+            .with_central_mass(1e30)
+            .with_counter_rotating_fraction(0.25)
+            .generate();
+
+        let counter_rotating = bodies[1..]
+            .iter()
+            .filter(|b| angular_momentum(b) < 0.0)
+            .count() as f64;
+
+        assert!((counter_rotating / 2000.0 - 0.25).abs() < 0.03);
+    }
+
+    #[test]
+    fn samples_surface_density_profiles() {
+        for (profile, expected_median_radius) in [
+            (Profile::Exponential { scale_length: 2.0 }, 2.0 * 1.678),
+            (Profile::Mestel { radius: 10.0 }, 5.0),
+        ] {
+            let bodies = Disk::new(4000, 1.0, profile, G).with_seed(2).generate();
+
+            let mut radii: Vec<f64> = bodies.iter().map(|b| b.pos().length()).collect();
+            radii.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let median_radius = radii[radii.len() / 2];
+
+            assert!(
+                (median_radius - expected_median_radius).abs() / expected_median_radius < 0.05,
+                "{}",
+                median_radius
+            );
+        }
+    }
+
+    #[test]
+    fn spreads_masses_around_mean() {
+        let bodies = Disk::new(1000, 1e30, Profile::Mestel { radius: 1e12 }, G)
+            .with_central_mass(1e32)
+            .with_mass_spread(0.5)
+            .generate();
+        let masses: Vec<f64> = bodies[1..].iter().map(|b| b.mass()).collect();
+
+        assert_eq!(bodies[0].mass(), 1e32);
+        assert!((masses.iter().sum::<f64>() - 1e30).abs() < 1e18);
+        assert!(masses.iter().all(|&m| m > 0.45e27 && m < 1.55e27));
+        assert!(masses.iter().any(|&m| m < 0.6e27) && masses.iter().any(|&m| m > 1.4e27));
+    }
+
+    #[test]
+    fn adds_halo_to_circular_velocity() {
+        let disk = Disk::new(10, 1e20, Profile::Mestel { radius: 1e12 }, G);
+        let with_halo = disk.clone().with_halo(Halo::new(2e5, 1e10));
+
+        let r = 5e11;
+        assert!(
+            (with_halo.circular_velocity(r).powi(2)
+                - disk.circular_velocity(r).powi(2)
+                - 2e5f64.powi(2) * r.powi(2) / (r.powi(2) + 1e20))
+                .abs()
+                < 1e-3
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{body::Body, ic::to_center_of_mass_frame, vec2::Vec2};
+use std::{error::Error, f64::consts::PI, fmt};
+
+pub const PRIMARY: usize = 0;
+pub const SECONDARY: usize = 1;
+
+/// Two galaxies approaching each other on a Kepler orbit of their centers
+/// of mass, with periapsis along the positive x axis.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Merger {
+    primary: Vec<Body>,
+    secondary: Vec<Body>,
+    pericenter: f64,
+    g: f64,
+    eccentricity: f64,
+    inclination: f64,
+    mass_ratio: Option<f64>,
+    separation: Option<f64>,
+}
+
+impl Merger {
+    pub fn new(primary: Vec<Body>, secondary: Vec<Body>, pericenter: f64, g: f64) -> Merger {
+        Merger {
+            primary,
+            secondary,
+            pericenter,
+            g,
+            eccentricity: 1.0,
+            inclination: 0.0,
+            mass_ratio: None,
+            separation: None,
+        }
+    }
+
+    /// Defaults to 1, a parabolic encounter.
+    pub fn with_eccentricity(mut self, eccentricity: f64) -> Merger {
+        self.eccentricity = eccentricity;
+        self
+    }
+
+    /// Angle the secondary galaxy is rotated by in the plane.
+    pub fn with_inclination(mut self, inclination: f64) -> Merger {
+        self.inclination = inclination;
+        self
+    }
+
+    /// Rescales the secondary so that its mass is `mass_ratio` times the
+    /// primary's, scaling velocities to keep it in equilibrium.
+    pub fn with_mass_ratio(mut self, mass_ratio: f64) -> Merger {
+        self.mass_ratio = Some(mass_ratio);
+        self
+    }
+
+    /// Initial distance between the centers of mass. Defaults to ten times
+    /// the pericenter, or the apocenter for closer bound orbits. It has to
+    /// lie between the pericenter and, for bound orbits, the apocenter.
+    pub fn with_separation(mut self, separation: f64) -> Merger {
+        self.separation = Some(separation);
+        self
+    }
+
+    pub fn build(&self) -> Result<Encounter, UnreachableSeparation> {
+        let primary_mass = total_mass(&self.primary);
+        let secondary_scale = self.mass_ratio.map_or(1.0, |ratio| {
+            ratio * primary_mass / total_mass(&self.secondary)
+        });
+        let secondary_mass = secondary_scale * total_mass(&self.secondary);
+        let mass = primary_mass + secondary_mass;
+
+        let semi_latus_rectum = self.pericenter * (1.0 + self.eccentricity);
+        let apocenter = if self.eccentricity < 1.0 {
+            Some(semi_latus_rectum / (1.0 - self.eccentricity))
+        } else {
+            None
+        };
+        let separation = self.separation.unwrap_or_else(|| {
+            apocenter.map_or(10.0 * self.pericenter, |apocenter| {
+                (10.0 * self.pericenter).min(apocenter)
+            })
+        });
+        let reachable = separation >= self.pericenter
+            && apocenter
+                .into_iter()
+                .all(|apocenter| separation <= apocenter);
+        if !reachable {
+            return Err(UnreachableSeparation {
+                separation,
+                pericenter: self.pericenter,
+                apocenter,
+            });
+        }
+
+        // True anomaly on the incoming branch of the orbit. Circular orbits
+        // start where bound ones start by default, at the apocenter.
+        let true_anomaly = if self.eccentricity == 0.0 {
+            -PI
+        } else {
+            // Rounding can push the cosine just past ±1 at the apsides.
+            -((semi_latus_rectum / separation - 1.0) / self.eccentricity)
+                .clamp(-1.0, 1.0)
+                .acos()
+        };
+        let (sin, cos) = true_anomaly.sin_cos();
+
+        let relative_pos = Vec2::new(cos, sin) * separation;
+        let relative_velocity =
+            Vec2::new(-sin, self.eccentricity + cos) * (self.g * mass / semi_latus_rectum).sqrt();
+
+        let mut bodies = Vec::with_capacity(self.primary.len() + self.secondary.len());
+        let mut galaxy_ids = Vec::with_capacity(bodies.capacity());
+
+        for (galaxy_id, galaxy, mass_scale, angle, fraction) in [
+            (PRIMARY, &self.primary, 1.0, 0.0, -secondary_mass / mass),
+            (
+                SECONDARY,
+                &self.secondary,
+                secondary_scale,
+                self.inclination,
+                primary_mass / mass,
+            ),
+        ] {
+            let mut galaxy = galaxy.clone();
+            to_center_of_mass_frame(&mut galaxy);
+
+            let velocity_scale = mass_scale.sqrt();
+            for body in galaxy {
+                bodies.push(
+                    Body::new(
+                        body.pos().rotate(angle) + relative_pos.clone() * fraction,
+                        body.velocity().rotate(angle) * velocity_scale
+                            + relative_velocity.clone() * fraction,
+                        body.mass() * mass_scale,
+                    )
+                    .with_charge(body.charge())
+                    .with_radius(body.radius())
+                    .with_test_particle(body.is_test_particle()),
+                );
+                galaxy_ids.push(galaxy_id);
+            }
+        }
+
+        Ok(Encounter { bodies, galaxy_ids })
+    }
+}
+
+/// Separation a `Merger` orbit never reaches.
+#[derive(PartialEq, Debug, Clone)]
+pub struct UnreachableSeparation {
+    separation: f64,
+    pericenter: f64,
+    apocenter: Option<f64>,
+}
+
+impl UnreachableSeparation {
+    pub fn separation(&self) -> f64 {
+        self.separation
+    }
+
+    pub fn pericenter(&self) -> f64 {
+        self.pericenter
+    }
+
+    /// `None` for unbound orbits.
+    pub fn apocenter(&self) -> Option<f64> {
+        self.apocenter
+    }
+}
+
+impl fmt::Display for UnreachableSeparation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "separation {} is outside the orbit, from pericenter {} to apocenter ",
+            self.separation, self.pericenter
+        )?;
+        match self.apocenter {
+            Some(apocenter) => write!(f, "{}", apocenter),
+            None => write!(f, "infinity"),
+        }
+    }
+}
+
+impl Error for UnreachableSeparation {}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Encounter {
+    bodies: Vec<Body>,
+    galaxy_ids: Vec<usize>,
+}
+
+impl Encounter {
+    pub fn bodies(&self) -> &[Body] {
+        &self.bodies
+    }
+
+    /// `PRIMARY` or `SECONDARY` for each body.
+    pub fn galaxy_ids(&self) -> &[usize] {
+        &self.galaxy_ids
+    }
+
+    pub fn galaxy(&self, galaxy_id: usize) -> Vec<&Body> {
+        self.bodies
+            .iter()
+            .zip(&self.galaxy_ids)
+            .filter(|(_, &id)| id == galaxy_id)
+            .map(|(body, _)| body)
+            .collect()
+    }
+
+    pub fn into_bodies(self) -> Vec<Body> {
+        self.bodies
+    }
+}
+
+// Test particles exert no force and leave the orbit alone.
+fn total_mass(bodies: &[Body]) -> f64 {
+    bodies
+        .iter()
+        .filter(|b| !b.is_test_particle())
+        .map(|b| b.mass())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ic::{
+        center_of_mass,
+        disk::{Disk, Profile},
+        plummer::Plummer,
+        Generator,
+    };
+    use crate::units::G;
+
+    fn galaxy_center(encounter: &Encounter, galaxy_id: usize) -> (Vec2, Vec2) {
+        center_of_mass(
+            &encounter
+                .galaxy(galaxy_id)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn merger() -> Merger {
+        let primary = Disk::new(200, 1e30, Profile::Exponential { scale_length: 1e12 }, G)
+            .with_central_mass(1e30)
+            .with_seed(1)
+            .generate();
+        let secondary = Plummer::new(100, 5e29, 5e11, G).with_seed(2).generate();
+
+        Merger::new(primary, secondary, 4e12, G)
+    }
+
+    #[test]
+    fn marks_galaxy_of_each_body() {
+        let encounter = merger().build().unwrap();
+
+        assert_eq!(encounter.bodies().len(), 301);
+        assert_eq!(encounter.galaxy(PRIMARY).len(), 201);
+        assert_eq!(encounter.galaxy(SECONDARY).len(), 100);
+        assert!(encounter.galaxy_ids()[..201]
+            .iter()
+            .all(|&id| id == PRIMARY));
+    }
+
+    #[test]
+    fn keeps_charges_and_test_particles() {
+        let primary = vec![Body::new(Vec2::zero(), Vec2::zero(), 1e30).with_charge(2.0)];
+        let secondary = vec![
+            Body::new(Vec2::zero(), Vec2::zero(), 5e29),
+            Body::new(Vec2::new(1e11, 0.0), Vec2::zero(), 1.0).with_test_particle(true),
+        ];
+
+        let encounter = Merger::new(primary.clone(), secondary.clone(), 4e12, G)
+            .with_mass_ratio(0.5)
+            .build()
+            .unwrap();
+        let charges: Vec<f64> = encounter.bodies().iter().map(|b| b.charge()).collect();
+        let test_particles: Vec<bool> = encounter
+            .bodies()
+            .iter()
+            .map(|b| b.is_test_particle())
+            .collect();
+
+        assert_eq!(charges, vec![2.0, 0.0, 0.0]);
+        assert_eq!(test_particles, vec![false, false, true]);
+
+        // However heavy, the test particle moves neither the orbit nor the
+        // center of mass.
+        let heavy_tracer =
+            Body::new(Vec2::new(1e11, 0.0), Vec2::zero(), 1e31).with_test_particle(true);
+        let heavy = Merger::new(
+            primary.clone(),
+            vec![secondary[0].clone(), heavy_tracer],
+            4e12,
+            G,
+        )
+        .with_mass_ratio(0.5)
+        .build()
+        .unwrap();
+        let without = Merger::new(primary, secondary[..1].to_vec(), 4e12, G)
+            .with_mass_ratio(0.5)
+            .build()
+            .unwrap();
+
+        assert_eq!(&heavy.bodies()[..2], without.bodies());
+        assert_eq!(&encounter.bodies()[..2], without.bodies());
+        let (pos, velocity) = center_of_mass(heavy.bodies());
+        assert!(pos.length() < 1.0);
+        assert!(velocity.length() < 1e-9);
+    }
+
+    #[test]
+    fn places_galaxies_on_parabolic_orbit() {
+        let encounter = merger().with_separation(2e13).build().unwrap();
+
+        let (pos, velocity) = center_of_mass(encounter.bodies());
+        assert!(pos.length() < 1.0);
+        assert!(velocity.length() < 1e-9);
+
+        let (primary_pos, primary_velocity) = galaxy_center(&encounter, PRIMARY);
+        let (secondary_pos, secondary_velocity) = galaxy_center(&encounter, SECONDARY);
+        let relative_pos = secondary_pos - primary_pos;
+        let relative_velocity = secondary_velocity - primary_velocity;
+
+        assert!((relative_pos.length() - 2e13).abs() / 2e13 < 1e-9);
+        // Parabolic orbits have zero energy.
+        let escape_speed = (2.0 * G * 2.5e30 / 2e13).sqrt();
+        assert!((relative_velocity.length() - escape_speed).abs() / escape_speed < 1e-9);
+        // Approaching, with periapsis ahead.
+        assert!(relative_pos.dot(&relative_velocity) < 0.0);
+        assert!(relative_pos.y() < 0.0);
+    }
+
+    #[test]
+    fn places_galaxies_on_circular_orbit() {
+        let encounter = merger().with_eccentricity(0.0).build().unwrap();
+        assert!(encounter
+            .bodies()
+            .iter()
+            .all(|b| b.pos().length().is_finite() && b.velocity().length().is_finite()));
+
+        let (primary_pos, primary_velocity) = galaxy_center(&encounter, PRIMARY);
+        let (secondary_pos, secondary_velocity) = galaxy_center(&encounter, SECONDARY);
+        let relative_pos = secondary_pos - primary_pos;
+        let relative_velocity = secondary_velocity - primary_velocity;
+
+        assert!((relative_pos.length() - 4e12).abs() / 4e12 < 1e-9);
+        let circular_speed = (G * 2.5e30 / 4e12).sqrt();
+        assert!((relative_velocity.length() - circular_speed).abs() / circular_speed < 1e-9);
+        assert!(relative_pos.dot(&relative_velocity).abs() < 1e-9 * 4e12 * circular_speed);
+    }
+
+    #[test]
+    fn rejects_unreachable_separations() {
+        let error = merger()
+            .with_eccentricity(0.0)
+            .with_separation(5e12)
+            .build()
+            .unwrap_err();
+        assert_eq!(error.apocenter(), Some(4e12));
+
+        // Bound orbits turn around at three times the pericenter.
+        let error = merger()
+            .with_eccentricity(0.5)
+            .with_separation(2e13)
+            .build()
+            .unwrap_err();
+        assert_eq!(error.separation(), 2e13);
+        assert!((error.apocenter().unwrap() - 1.2e13).abs() < 1.0);
+        assert!(merger()
+            .with_eccentricity(0.5)
+            .with_separation(1.2e13)
+            .build()
+            .is_ok());
+
+        let error = merger().with_separation(1e12).build().unwrap_err();
+        assert_eq!(error.pericenter(), 4e12);
+        assert_eq!(error.apocenter(), None);
+    }
+
+    #[test]
+    fn rescales_secondary_to_mass_ratio() {
+        let encounter = merger().with_mass_ratio(0.25).build().unwrap();
+
+        let mass = |id| encounter.galaxy(id).iter().map(|b| b.mass()).sum::<f64>();
+        assert!((mass(SECONDARY) / mass(PRIMARY) - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rotates_secondary_by_inclination() {
+        let encounter = merger().build().unwrap();
+        let inclined_encounter = merger()
+            .with_inclination(std::f64::consts::PI)
+            .build()
+            .unwrap();
+
+        let (secondary_pos, _) = galaxy_center(&encounter, SECONDARY);
+        let (inclined_secondary_pos, _) = galaxy_center(&inclined_encounter, SECONDARY);
+        assert!((secondary_pos.clone() - inclined_secondary_pos.clone()).length() < 1.0);
+
+        for (body, inclined_body) in encounter
+            .galaxy(SECONDARY)
+            .iter()
+            .zip(inclined_encounter.galaxy(SECONDARY))
+        {
+            let offset = body.pos().clone() - secondary_pos.clone();
+            let inclined_offset = inclined_body.pos().clone() - inclined_secondary_pos.clone();
+
+            assert!((offset.clone() + inclined_offset).length() < 1e-6 * offset.length());
+        }
+    }
+}
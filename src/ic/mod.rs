@@ -0,0 +1,135 @@
+pub mod disk;
+pub mod merger;
+pub mod plummer;
+
+use crate::{body::Body, vec2::Vec2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Generators draw every random number from the given `Rng`, so the same
+/// seed always produces the same bodies.
+pub trait Generator {
+    fn seed(&self) -> u64;
+
+    fn generate_with_rng<R: Rng>(&self, rng: &mut R) -> Vec<Body>;
+
+    fn generate(&self) -> Vec<Body> {
+        self.generate_with_rng(&mut seeded_rng(self.seed()))
+    }
+}
+
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Position and velocity of the center of mass of the massive bodies, or
+/// zero if there are none. Test particles are left out.
+pub fn center_of_mass<'a>(bodies: impl IntoIterator<Item = &'a Body>) -> (Vec2, Vec2) {
+    let (mass, pos, velocity) = bodies.into_iter().filter(|b| !b.is_test_particle()).fold(
+        (0.0, Vec2::zero(), Vec2::zero()),
+        |(m, p, v), b| {
+            (
+                m + b.mass(),
+                p + b.pos().clone() * b.mass(),
+                v + b.velocity().clone() * b.mass(),
+            )
+        },
+    );
+
+    if mass > 0.0 {
+        (pos / mass, velocity / mass)
+    } else {
+        (Vec2::zero(), Vec2::zero())
+    }
+}
+
+pub fn to_center_of_mass_frame(bodies: &mut [Body]) {
+    let (pos, velocity) = center_of_mass(bodies.iter());
+
+    for body in bodies {
+        body.translate(pos.clone() * -1.0);
+        body.set_velocity(body.velocity().clone() - velocity.clone());
+    }
+}
+
+pub fn kinetic_energy(bodies: &[Body]) -> f64 {
+    bodies
+        .iter()
+        .map(|b| 0.5 * b.mass() * b.velocity().dot(b.velocity()))
+        .sum()
+}
+
+pub fn potential_energy(bodies: &[Body], g: f64) -> f64 {
+    let mut energy = 0.0;
+
+    for (i, body) in bodies.iter().enumerate() {
+        for other_body in &bodies[(i + 1)..] {
+            energy -= g * body.mass() * other_body.mass() / body.pos().dist(other_body.pos());
+        }
+    }
+
+    energy
+}
+
+/// Scales velocities so that the kinetic energy is `ratio` times the
+/// magnitude of the potential energy; 0.5 is virial equilibrium.
+pub fn virialize(bodies: &mut [Body], ratio: f64, g: f64) {
+    let kinetic_energy = kinetic_energy(bodies);
+    if kinetic_energy == 0.0 {
+        return;
+    }
+
+    let scale = (ratio * potential_energy(bodies, g).abs() / kinetic_energy).sqrt();
+
+    for body in bodies {
+        body.set_velocity(body.velocity().clone() * scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::G;
+
+    #[test]
+    fn moves_bodies_to_center_of_mass_frame() {
+        let mut bodies = vec![
+            Body::new(Vec2::new(1.0, 2.0), Vec2::new(3.0, 0.0), 1.0),
+            Body::new(Vec2::new(5.0, -2.0), Vec2::new(-1.0, 4.0), 3.0),
+        ];
+
+        to_center_of_mass_frame(&mut bodies);
+
+        assert_eq!(center_of_mass(&bodies), (Vec2::zero(), Vec2::zero()));
+        assert_eq!(bodies[0].pos(), &Vec2::new(-3.0, 3.0));
+        assert_eq!(bodies[1].velocity(), &Vec2::new(-1.0, 1.0));
+    }
+
+    #[test]
+    fn leaves_test_particles_out_of_center_of_mass() {
+        let bodies = [
+            Body::new(Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0), 1.0),
+            Body::new(Vec2::new(5.0, 4.0), Vec2::new(-2.0, 4.0), 3.0),
+            Body::new(Vec2::new(-50.0, 9.0), Vec2::unit(), 8.0).with_test_particle(true),
+        ];
+
+        assert_eq!(
+            center_of_mass(&bodies),
+            (Vec2::new(4.0, 3.0), Vec2::new(-1.0, 3.0))
+        );
+        assert_eq!(center_of_mass(&bodies[2..]), (Vec2::zero(), Vec2::zero()));
+        assert_eq!(center_of_mass(&[]), (Vec2::zero(), Vec2::zero()));
+    }
+
+    #[test]
+    fn virializes_bodies() {
+        let mut bodies = vec![
+            Body::new(Vec2::new(-1.0, 0.0), Vec2::new(0.0, 3.0), 1e10),
+            Body::new(Vec2::new(1.0, 0.0), Vec2::new(0.0, -3.0), 1e10),
+        ];
+
+        virialize(&mut bodies, 0.5, G);
+
+        let ratio = kinetic_energy(&bodies) / potential_energy(&bodies, G).abs();
+        assert!((ratio - 0.5).abs() < 1e-12);
+    }
+}
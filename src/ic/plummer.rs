@@ -0,0 +1,136 @@
+use crate::{
+    body::Body,
+    ic::{to_center_of_mass_frame, virialize, Generator},
+    vec2::Vec2,
+};
+use rand::Rng;
+use std::f64::consts::PI;
+
+// Mass fraction above which radii are not sampled, to avoid a few bodies
+// being placed arbitrarily far away.
+const MAX_MASS_FRACTION: f64 = 0.999;
+
+/// Plummer sphere sampled as in Aarseth, Hénon & Wielen (1974) and
+/// projected onto the plane, with equal-mass bodies.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Plummer {
+    size: usize,
+    total_mass: f64,
+    scale_radius: f64,
+    g: f64,
+    seed: u64,
+}
+
+impl Plummer {
+    pub fn new(size: usize, total_mass: f64, scale_radius: f64, g: f64) -> Plummer {
+        Plummer {
+            size,
+            total_mass,
+            scale_radius,
+            g,
+            seed: 0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Plummer {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Generator for Plummer {
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn generate_with_rng<R: Rng>(&self, rng: &mut R) -> Vec<Body> {
+        let mass = self.total_mass / self.size as f64;
+        let escape_speed_scale = (2.0 * self.g * self.total_mass / self.scale_radius).sqrt();
+
+        let mut bodies: Vec<Body> = (0..self.size)
+            .map(|_| {
+                let mass_fraction = rng.gen_range(0.0, MAX_MASS_FRACTION);
+                let radius = 1.0 / (mass_fraction.powf(-2.0 / 3.0) - 1.0).sqrt();
+
+                let escape_speed = escape_speed_scale * (1.0 + radius.powi(2)).powf(-0.25);
+                let speed = escape_speed * sample_speed_fraction(rng);
+
+                Body::new(
+                    isotropic(rng) * (radius * self.scale_radius),
+                    isotropic(rng) * speed,
+                    mass,
+                )
+            })
+            .collect();
+
+        to_center_of_mass_frame(&mut bodies);
+        virialize(&mut bodies, 0.5, self.g);
+
+        bodies
+    }
+}
+
+// Samples q = v / v_escape from g(q) = q^2 (1 - q^2)^(7/2) by rejection.
+fn sample_speed_fraction<R: Rng>(rng: &mut R) -> f64 {
+    loop {
+        let q: f64 = rng.gen_range(0.0, 1.0);
+        let g: f64 = rng.gen_range(0.0, 0.1);
+
+        if g < q.powi(2) * (1.0 - q.powi(2)).powf(3.5) {
+            return q;
+        }
+    }
+}
+
+// Projection onto the plane of a uniformly random unit vector in 3D.
+fn isotropic<R: Rng>(rng: &mut R) -> Vec2 {
+    let z: f64 = rng.gen_range(-1.0, 1.0);
+    let phi = rng.gen_range(0.0, 2.0 * PI);
+    let planar = (1.0 - z.powi(2)).sqrt();
+
+    Vec2::new(planar * phi.cos(), planar * phi.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ic::{center_of_mass, kinetic_energy, potential_energy},
+        units::G,
+    };
+
+    #[test]
+    fn generates_same_bodies_for_same_seed() {
+        let plummer = Plummer::new(100, 1e30, 1e12, G).with_seed(3);
+
+        assert_eq!(plummer.generate(), plummer.generate());
+        assert_ne!(plummer.generate(), plummer.with_seed(4).generate());
+    }
+
+    #[test]
+    fn generates_virialized_bodies_at_rest_at_origin() {
+        let bodies = Plummer::new(500, 1e30, 1e12, G).with_seed(1).generate();
+
+        assert_eq!(bodies.len(), 500);
+        assert!((bodies.iter().map(|b| b.mass()).sum::<f64>() - 1e30).abs() < 1e18);
+
+        let (pos, velocity) = center_of_mass(&bodies);
+        assert!(pos.length() < 1e-3);
+        assert!(velocity.length() < 1e-12);
+
+        let ratio = kinetic_energy(&bodies) / potential_energy(&bodies, G).abs();
+        assert!((ratio - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn generates_projected_plummer_profile() {
+        let bodies = Plummer::new(2000, 1.0, 2.0, G).with_seed(1).generate();
+
+        // Half of the projected mass lies within the scale radius.
+        let mut radii: Vec<f64> = bodies.iter().map(|b| b.pos().length()).collect();
+        radii.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let half_mass_radius = radii[radii.len() / 2];
+
+        assert!((half_mass_radius - 2.0).abs() < 0.2, "{}", half_mass_radius);
+    }
+}
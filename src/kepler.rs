@@ -0,0 +1,307 @@
+use crate::{body::Body, vec2::Vec2};
+use std::f64::consts::PI;
+
+const TOLERANCE: f64 = 1e-14;
+const MAX_ITERATIONS: usize = 100;
+
+/// Solves Kepler's equation for the eccentric anomaly, `M = E - e sin E`
+/// when `eccentricity < 1` and `M = e sinh H - H` when it is greater.
+pub fn eccentric_anomaly(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    if eccentricity < 1.0 {
+        let mean_anomaly = normalize_angle(mean_anomaly + PI) - PI;
+        let mut anomaly = if eccentricity < 0.8 {
+            mean_anomaly
+        } else {
+            PI * mean_anomaly.signum()
+        };
+
+        for _ in 0..MAX_ITERATIONS {
+            let delta = (anomaly - eccentricity * anomaly.sin() - mean_anomaly)
+                / (1.0 - eccentricity * anomaly.cos());
+            anomaly -= delta;
+
+            if delta.abs() < TOLERANCE {
+                break;
+            }
+        }
+
+        anomaly
+    } else {
+        let mut anomaly = (mean_anomaly / eccentricity).asinh();
+
+        for _ in 0..MAX_ITERATIONS {
+            let delta = (eccentricity * anomaly.sinh() - anomaly - mean_anomaly)
+                / (eccentricity * anomaly.cosh() - 1.0);
+            anomaly -= delta;
+
+            if delta.abs() < TOLERANCE * anomaly.abs().max(1.0) {
+                break;
+            }
+        }
+
+        anomaly
+    }
+}
+
+/// Planar orbit of a body around a primary. Elliptic orbits have a positive
+/// semi-major axis and hyperbolic ones a negative one; parabolic orbits are
+/// not representable. Orbits are counterclockwise unless retrograde.
+#[derive(PartialEq, Debug, Clone)]
+pub struct OrbitalElements {
+    semi_major_axis: f64,
+    eccentricity: f64,
+    argument_of_periapsis: f64,
+    mean_anomaly: f64,
+    retrograde: bool,
+}
+
+impl OrbitalElements {
+    pub fn new(
+        semi_major_axis: f64,
+        eccentricity: f64,
+        argument_of_periapsis: f64,
+        mean_anomaly: f64,
+    ) -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis,
+            eccentricity,
+            argument_of_periapsis,
+            mean_anomaly,
+            retrograde: false,
+        }
+    }
+
+    pub fn with_retrograde(mut self, retrograde: bool) -> OrbitalElements {
+        self.retrograde = retrograde;
+        self
+    }
+
+    pub fn from_state(primary: &Body, body: &Body, g: f64) -> OrbitalElements {
+        let mu = g * (primary.mass() + body.mass());
+        let pos = body.pos().clone() - primary.pos().clone();
+        let velocity = body.velocity().clone() - primary.velocity().clone();
+
+        let retrograde = pos.x() * velocity.y() - pos.y() * velocity.x() < 0.0;
+        let (pos, velocity) = if retrograde {
+            (mirror(&pos), mirror(&velocity))
+        } else {
+            (pos, velocity)
+        };
+
+        let r = pos.length();
+        let radial_velocity = pos.dot(&velocity);
+        let semi_major_axis = 1.0 / (2.0 / r - velocity.dot(&velocity) / mu);
+
+        let eccentricity_vector =
+            (pos.clone() * (velocity.dot(&velocity) - mu / r) - velocity * radial_velocity) / mu;
+        let eccentricity = eccentricity_vector.length();
+
+        if eccentricity < TOLERANCE {
+            return OrbitalElements::new(
+                semi_major_axis,
+                0.0,
+                0.0,
+                normalize_angle(pos.y().atan2(pos.x())),
+            )
+            .with_retrograde(retrograde);
+        }
+
+        let argument_of_periapsis = eccentricity_vector.y().atan2(eccentricity_vector.x());
+        let mean_anomaly = if eccentricity < 1.0 {
+            let anomaly =
+                (radial_velocity / (mu * semi_major_axis).sqrt()).atan2(1.0 - r / semi_major_axis);
+            normalize_angle(anomaly - eccentricity * anomaly.sin())
+        } else {
+            let anomaly =
+                (radial_velocity / (eccentricity * (-mu * semi_major_axis).sqrt())).asinh();
+            eccentricity * anomaly.sinh() - anomaly
+        };
+
+        OrbitalElements::new(
+            semi_major_axis,
+            eccentricity,
+            normalize_angle(argument_of_periapsis),
+            mean_anomaly,
+        )
+        .with_retrograde(retrograde)
+    }
+
+    pub fn semi_major_axis(&self) -> f64 {
+        self.semi_major_axis
+    }
+
+    pub fn eccentricity(&self) -> f64 {
+        self.eccentricity
+    }
+
+    pub fn argument_of_periapsis(&self) -> f64 {
+        self.argument_of_periapsis
+    }
+
+    pub fn mean_anomaly(&self) -> f64 {
+        self.mean_anomaly
+    }
+
+    pub fn retrograde(&self) -> bool {
+        self.retrograde
+    }
+
+    pub fn period(&self, mu: f64) -> f64 {
+        2.0 * PI * (self.semi_major_axis.powi(3) / mu).sqrt()
+    }
+
+    /// Position and velocity relative to the primary, with `mu` the
+    /// gravitational parameter `G (m_primary + m_body)`.
+    pub fn to_relative_state(&self, mu: f64) -> (Vec2, Vec2) {
+        let e = self.eccentricity;
+        let a = self.semi_major_axis.abs();
+        let mean_motion = (mu / a.powi(3)).sqrt();
+        let anomaly = eccentric_anomaly(self.mean_anomaly, e);
+
+        let (pos, velocity) = if e < 1.0 {
+            let (sin, cos) = anomaly.sin_cos();
+            let factor = a * mean_motion / (1.0 - e * cos);
+            let minor = (1.0 - e.powi(2)).sqrt();
+
+            (
+                Vec2::new(a * (cos - e), a * minor * sin),
+                Vec2::new(-factor * sin, factor * minor * cos),
+            )
+        } else {
+            let (sinh, cosh) = (anomaly.sinh(), anomaly.cosh());
+            let factor = a * mean_motion / (e * cosh - 1.0);
+            let minor = (e.powi(2) - 1.0).sqrt();
+
+            (
+                Vec2::new(a * (e - cosh), a * minor * sinh),
+                Vec2::new(-factor * sinh, factor * minor * cosh),
+            )
+        };
+
+        let pos = pos.rotate(self.argument_of_periapsis);
+        let velocity = velocity.rotate(self.argument_of_periapsis);
+
+        if self.retrograde {
+            (mirror(&pos), mirror(&velocity))
+        } else {
+            (pos, velocity)
+        }
+    }
+
+    pub fn to_body(&self, primary: &Body, mass: f64, g: f64) -> Body {
+        let (pos, velocity) = self.to_relative_state(g * (primary.mass() + mass));
+
+        Body::new(
+            primary.pos().clone() + pos,
+            primary.velocity().clone() + velocity,
+            mass,
+        )
+    }
+}
+
+fn mirror(vec: &Vec2) -> Vec2 {
+    Vec2::new(vec.x(), -vec.y())
+}
+
+fn normalize_angle(angle: f64) -> f64 {
+    angle.rem_euclid(2.0 * PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::G;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance * expected.abs().max(1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn solves_keplers_equation() {
+        for &e in &[0.0, 0.1, 0.5, 0.9, 0.99] {
+            for &m in &[0.0, 0.3, 1.0, 2.5, 3.1, 5.0] {
+                let anomaly = eccentric_anomaly(m, e);
+                assert_close(
+                    normalize_angle(anomaly - e * anomaly.sin()),
+                    normalize_angle(m),
+                    1e-12,
+                );
+            }
+        }
+
+        for &e in &[1.1, 2.0, 5.0] {
+            for &m in &[-10.0, -0.5, 0.0, 1.0, 30.0] {
+                let anomaly = eccentric_anomaly(m, e);
+                assert_close(e * anomaly.sinh() - anomaly, m, 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn places_circular_orbit_at_circular_speed() {
+        let primary = Body::new(Vec2::new(1e11, 0.0), Vec2::new(0.0, 1e3), 2e30);
+        let body = OrbitalElements::new(1.5e11, 0.0, 0.0, PI / 2.0).to_body(&primary, 6e24, G);
+
+        let relative_pos = body.pos().clone() - primary.pos().clone();
+        let relative_velocity = body.velocity().clone() - primary.velocity().clone();
+
+        assert!(relative_pos.x().abs() < 1.0);
+        assert_close(relative_pos.y(), 1.5e11, 1e-12);
+        assert_close(
+            relative_velocity.length(),
+            (G * (2e30 + 6e24) / 1.5e11).sqrt(),
+            1e-12,
+        );
+        assert!(relative_velocity.x() < 0.0);
+    }
+
+    #[test]
+    fn places_body_at_periapsis() {
+        let primary = Body::new(Vec2::zero(), Vec2::zero(), 1e30);
+        let body = OrbitalElements::new(1e11, 0.5, PI / 2.0, 0.0).to_body(&primary, 0.0, G);
+
+        assert!(body.pos().x().abs() < 1.0);
+        assert_close(body.pos().y(), 5e10, 1e-12);
+    }
+
+    #[test]
+    fn converts_elements_back_and_forth() {
+        let primary = Body::new(Vec2::new(3e10, -2e10), Vec2::new(5e3, 1e3), 2e30);
+
+        for elements in [
+            OrbitalElements::new(1e11, 0.2, 1.0, 2.0),
+            OrbitalElements::new(5e11, 0.9, 4.0, 0.1),
+            OrbitalElements::new(2e11, 0.6, 0.5, 5.5).with_retrograde(true),
+            OrbitalElements::new(-1e11, 1.5, 2.0, 3.0),
+            OrbitalElements::new(-3e11, 3.0, 5.0, -1.0).with_retrograde(true),
+        ] {
+            let body = elements.to_body(&primary, 1e25, G);
+            let converted = OrbitalElements::from_state(&primary, &body, G);
+
+            assert_close(
+                converted.semi_major_axis(),
+                elements.semi_major_axis(),
+                1e-9,
+            );
+            assert_close(converted.eccentricity(), elements.eccentricity(), 1e-9);
+            assert_close(
+                converted.argument_of_periapsis(),
+                elements.argument_of_periapsis(),
+                1e-9,
+            );
+            assert_close(converted.mean_anomaly(), elements.mean_anomaly(), 1e-9);
+            assert_eq!(converted.retrograde(), elements.retrograde());
+
+            let round_trip = converted.to_body(&primary, 1e25, G);
+            assert_close(round_trip.pos().x(), body.pos().x(), 1e-9);
+            assert_close(round_trip.pos().y(), body.pos().y(), 1e-9);
+            assert_close(round_trip.velocity().x(), body.velocity().x(), 1e-9);
+            assert_close(round_trip.velocity().y(), body.velocity().y(), 1e-9);
+        }
+    }
+}
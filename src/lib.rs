@@ -1,3 +1,17 @@
+pub mod accuracy;
 pub mod body;
+pub mod collision;
+pub mod complex;
+pub mod fft;
+pub mod force_law;
+pub mod ic;
+pub mod kepler;
+pub mod periodic;
+pub mod potential;
+pub mod presets;
+pub mod render;
 pub mod simulation;
+pub mod snapshot;
+pub mod special;
+pub mod units;
 pub mod vec2;
@@ -0,0 +1,334 @@
+use super::PeriodicBox;
+use crate::{
+    body::Body,
+    force_law::{ForceLaw, MultipoleExpansion},
+    special::{erf, erfc},
+    vec2::Vec2,
+};
+use std::f64::consts::PI;
+
+// Images and wave vectors summed along each axis; with the splitting
+// parameter below both sums converge to rounding.
+const REAL_IMAGES: i32 = 3;
+const WAVE_VECTORS: i32 = 4;
+
+/// What bodies carry into an `Ewald` interaction.
+pub trait Source {
+    /// Product of the sources of both bodies, signed so that the pair
+    /// potential is `coupling / r` plus the periodic correction.
+    fn coupling(&self, body: &Body, other: &Body) -> f64;
+}
+
+/// Masses attracting with gravitational constant `g`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Mass {
+    g: f64,
+}
+
+impl Source for Mass {
+    fn coupling(&self, body: &Body, other: &Body) -> f64 {
+        -self.g * body.mass() * other.mass()
+    }
+}
+
+/// Signed charges with Coulomb constant `k`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Charge {
+    k: f64,
+}
+
+impl Source for Charge {
+    fn coupling(&self, body: &Body, other: &Body) -> f64 {
+        self.k * body.charge() * other.charge()
+    }
+}
+
+/// Inverse-distance interaction summed over all periodic images of a body
+/// by Ewald's method, against a uniform neutralizing background.
+///
+/// Pair potentials leave out each body's interaction with its own images,
+/// which adds a constant `coupling * self_potential() / 2` per body.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Ewald<S: Source = Mass> {
+    periodic_box: PeriodicBox,
+    source: S,
+    alpha: f64,
+    table: Option<CorrectionTable>,
+}
+
+impl Ewald {
+    /// Periodic Newtonian gravity.
+    pub fn new(periodic_box: PeriodicBox, g: f64) -> Ewald {
+        Ewald::with_source(periodic_box, Mass { g })
+    }
+}
+
+impl Ewald<Charge> {
+    /// Periodic electrostatics between signed charges.
+    pub fn coulomb(periodic_box: PeriodicBox, k: f64) -> Ewald<Charge> {
+        Ewald::with_source(periodic_box, Charge { k })
+    }
+}
+
+impl<S: Source> Ewald<S> {
+    fn with_source(periodic_box: PeriodicBox, source: S) -> Ewald<S> {
+        Ewald {
+            alpha: 2.0 / periodic_box.size(),
+            periodic_box,
+            source,
+            table: None,
+        }
+    }
+
+    /// Interpolates the difference from the nearest-image interaction on a
+    /// grid with `resolution` cells across half the box instead of summing
+    /// the series for every pair.
+    pub fn with_table(mut self, resolution: usize) -> Ewald<S> {
+        assert!(resolution > 0, "table needs at least one cell");
+        self.table = Some(CorrectionTable::new(&self, resolution));
+        self
+    }
+
+    pub fn periodic_box(&self) -> &PeriodicBox {
+        &self.periodic_box
+    }
+
+    /// Periodic Green's function of `separation` and its gradient.
+    pub fn lattice_sum(&self, separation: &Vec2) -> (f64, Vec2) {
+        let separation = self.periodic_box.nearest_image(separation);
+        let (potential, gradient) = self.correction(&separation);
+        let dist = separation.length();
+
+        (potential + 1.0 / dist, gradient - separation / dist.powi(3))
+    }
+
+    /// Interaction of a unit source with its own images.
+    pub fn self_potential(&self) -> f64 {
+        self.correction(&Vec2::zero()).0
+    }
+
+    // Lattice sum minus the nearest image, smooth inside the box.
+    fn correction(&self, separation: &Vec2) -> (f64, Vec2) {
+        let size = self.periodic_box.size();
+        let alpha = self.alpha;
+        let mut potential = -2.0 * PI.sqrt() / (size.powi(2) * alpha);
+        let mut gradient = Vec2::zero();
+
+        for i in -REAL_IMAGES..=REAL_IMAGES {
+            for j in -REAL_IMAGES..=REAL_IMAGES {
+                let image = separation.clone() + Vec2::new(i as f64, j as f64) * size;
+                let dist = image.length();
+                let gaussian = 2.0 * alpha / PI.sqrt() * (-(alpha * dist).powi(2)).exp();
+
+                let slope = if i == 0 && j == 0 {
+                    if dist == 0.0 {
+                        potential -= 2.0 * alpha / PI.sqrt();
+                        continue;
+                    }
+
+                    let screened = erf(alpha * dist) / dist;
+                    potential -= screened;
+                    (screened - gaussian) / dist
+                } else {
+                    let screened = erfc(alpha * dist) / dist;
+                    potential += screened;
+                    -(screened + gaussian) / dist
+                };
+
+                gradient = gradient + image * (slope / dist);
+            }
+        }
+
+        for i in -WAVE_VECTORS..=WAVE_VECTORS {
+            for j in -WAVE_VECTORS..=WAVE_VECTORS {
+                if i == 0 && j == 0 {
+                    continue;
+                }
+
+                let wave_vector = Vec2::new(i as f64, j as f64) * (2.0 * PI / size);
+                let wave_number = wave_vector.length();
+                let weight =
+                    2.0 * PI / size.powi(2) * erfc(wave_number / (2.0 * alpha)) / wave_number;
+                let phase = wave_vector.dot(separation);
+
+                potential += weight * phase.cos();
+                gradient = gradient - wave_vector * (weight * phase.sin());
+            }
+        }
+
+        (potential, gradient)
+    }
+}
+
+impl<S: Source> ForceLaw for Ewald<S> {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        let separation = self
+            .periodic_box
+            .nearest_image(&(body.pos().clone() - other.pos().clone()));
+        let dist = separation.length();
+
+        let (potential, gradient) = match &self.table {
+            Some(table) => table.correction(&separation),
+            None => self.correction(&separation),
+        };
+        let potential = potential + 1.0 / dist;
+        let gradient = gradient - separation / dist.powi(3);
+
+        let coupling = self.source.coupling(body, other);
+        (gradient * -coupling, coupling * potential)
+    }
+}
+
+// Like `Coulomb`, signed charges have no center to aggregate around.
+impl MultipoleExpansion for Ewald<Mass> {}
+
+// Correction on the quarter `[0, size / 2]²` of the box; the potential is
+// even and each gradient component odd in the matching coordinate.
+#[derive(PartialEq, Debug, Clone)]
+struct CorrectionTable {
+    spacing: f64,
+    resolution: usize,
+    values: Vec<(f64, Vec2)>,
+}
+
+impl CorrectionTable {
+    fn new<S: Source>(ewald: &Ewald<S>, resolution: usize) -> CorrectionTable {
+        let spacing = ewald.periodic_box.size() / 2.0 / resolution as f64;
+
+        let mut values = Vec::with_capacity((resolution + 1).pow(2));
+        for j in 0..=resolution {
+            for i in 0..=resolution {
+                values.push(ewald.correction(&Vec2::new(i as f64 * spacing, j as f64 * spacing)));
+            }
+        }
+
+        CorrectionTable {
+            spacing,
+            resolution,
+            values,
+        }
+    }
+
+    fn correction(&self, separation: &Vec2) -> (f64, Vec2) {
+        let cell = |x: f64| {
+            let x = (x.abs() / self.spacing).min(self.resolution as f64);
+            let i = (x.floor() as usize).min(self.resolution - 1);
+            (i, x - i as f64)
+        };
+        let (i, tx) = cell(separation.x());
+        let (j, ty) = cell(separation.y());
+
+        let value = |i: usize, j: usize| &self.values[j * (self.resolution + 1) + i];
+        let weights = [
+            ((i, j), (1.0 - tx) * (1.0 - ty)),
+            ((i + 1, j), tx * (1.0 - ty)),
+            ((i, j + 1), (1.0 - tx) * ty),
+            ((i + 1, j + 1), tx * ty),
+        ];
+
+        let mut potential = 0.0;
+        let mut gradient = Vec2::zero();
+        for &((i, j), weight) in weights.iter() {
+            let (p, g) = value(i, j);
+            potential += weight * p;
+            gradient = gradient + g.clone() * weight;
+        }
+
+        (
+            potential,
+            Vec2::new(
+                gradient.x() * separation.x().signum(),
+                gradient.y() * separation.y().signum(),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Σ' 1 / |n| over the unit square lattice against a neutralizing
+    // background, 4 ζ(1/2) β(1/2).
+    const SQUARE_LATTICE_SUM: f64 = -3.900_264_920_001_955;
+
+    #[test]
+    fn reproduces_square_lattice_sum() {
+        for &size in [1.0, 3.5].iter() {
+            let ewald = Ewald::new(PeriodicBox::new(Vec2::zero(), size), 1.0);
+
+            assert!((ewald.self_potential() * size - SQUARE_LATTICE_SUM).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn is_periodic_and_symmetric() {
+        let ewald = Ewald::new(PeriodicBox::new(Vec2::zero(), 2.0), 1.0);
+        let separation = Vec2::new(0.3, -0.7);
+
+        let (potential, gradient) = ewald.lattice_sum(&separation);
+        let (image_potential, image_gradient) =
+            ewald.lattice_sum(&(separation.clone() + Vec2::new(-4.0, 2.0)));
+        assert!((potential - image_potential).abs() < 1e-12);
+        assert!(gradient.dist(&image_gradient) < 1e-12);
+
+        // Halfway to the next image the pulls from both sides cancel.
+        let (_, gradient) = ewald.lattice_sum(&Vec2::new(1.0, 0.3));
+        assert!(gradient.x().abs() < 1e-12);
+    }
+
+    #[test]
+    fn derives_forces_from_potentials() {
+        let ewald = Ewald::new(PeriodicBox::new(Vec2::zero(), 2.0), 1.5);
+        let body = Body::new(Vec2::new(0.2, 0.4), Vec2::zero(), 2.0);
+        let other = Body::new(Vec2::new(-0.7, -0.5), Vec2::zero(), 3.0);
+
+        let h = 1e-6;
+        let potential_at = |offset: Vec2| {
+            let mut moved = body.clone();
+            moved.translate(offset);
+            ewald.interact(&moved, &other).1
+        };
+        let gradient = Vec2::new(
+            (potential_at(Vec2::new(h, 0.0)) - potential_at(Vec2::new(-h, 0.0))) / (2.0 * h),
+            (potential_at(Vec2::new(0.0, h)) - potential_at(Vec2::new(0.0, -h))) / (2.0 * h),
+        );
+        let (force, _) = ewald.interact(&body, &other);
+
+        assert!((force.clone() + gradient).length() < 1e-6 * force.length());
+        assert!(force.x() < 0.0 && force.y() < 0.0);
+    }
+
+    #[test]
+    fn interpolates_correction_table() {
+        let direct = Ewald::new(PeriodicBox::new(Vec2::zero(), 2.0), 1.0);
+        let tabulated = direct.clone().with_table(64);
+        let body = Body::new(Vec2::zero(), Vec2::zero(), 1.0);
+
+        for &(x, y) in [(0.1, 0.2), (-0.9, 0.35), (0.99, -0.99), (0.0, -0.5)].iter() {
+            let other = Body::new(Vec2::new(x, y), Vec2::zero(), 1.0);
+            let (direct_force, direct_potential) = direct.interact(&body, &other);
+            let (force, potential) = tabulated.interact(&body, &other);
+
+            assert!(force.dist(&direct_force) < 5e-3 * direct_force.length());
+            assert!((potential - direct_potential).abs() < 5e-3 * direct_potential.abs());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "table needs at least one cell")]
+    fn rejects_empty_correction_table() {
+        Ewald::new(PeriodicBox::new(Vec2::zero(), 2.0), 1.0).with_table(0);
+    }
+
+    #[test]
+    fn repels_like_charges() {
+        let ewald = Ewald::coulomb(PeriodicBox::new(Vec2::zero(), 2.0), 1.0);
+        let body = Body::new(Vec2::zero(), Vec2::zero(), 1.0).with_charge(1.0);
+        let other = Body::new(Vec2::new(0.2, 0.0), Vec2::zero(), 1.0).with_charge(1.0);
+
+        let (force, potential) = ewald.interact(&body, &other);
+        assert!(force.x() < 0.0);
+        assert!(potential > 0.0);
+    }
+}
@@ -0,0 +1,146 @@
+pub mod ewald;
+
+use crate::{
+    body::Body,
+    force_law::{ForceLaw, MultipoleExpansion},
+    simulation::barnes_hut::quad::Quad,
+    vec2::Vec2,
+};
+
+/// Square domain repeated infinitely in both directions.
+#[derive(PartialEq, Debug, Clone)]
+pub struct PeriodicBox {
+    center: Vec2,
+    size: f64,
+}
+
+impl PeriodicBox {
+    pub fn new(center: Vec2, size: f64) -> PeriodicBox {
+        PeriodicBox { center, size }
+    }
+
+    pub fn center(&self) -> &Vec2 {
+        &self.center
+    }
+
+    pub fn size(&self) -> f64 {
+        self.size
+    }
+
+    pub fn quad(&self) -> Quad {
+        Quad::new(self.center.clone(), self.size)
+    }
+
+    /// Image of `pos` inside the box.
+    pub fn wrap(&self, pos: &Vec2) -> Vec2 {
+        let half_size = self.size / 2.0;
+        let wrap = |x: f64, center: f64| {
+            center + (x - center + half_size).rem_euclid(self.size) - half_size
+        };
+
+        Vec2::new(
+            wrap(pos.x(), self.center.x()),
+            wrap(pos.y(), self.center.y()),
+        )
+    }
+
+    /// Shortest of the separations equivalent to `separation`.
+    pub fn nearest_image(&self, separation: &Vec2) -> Vec2 {
+        let nearest = |x: f64| x - self.size * (x / self.size).round();
+
+        Vec2::new(nearest(separation.x()), nearest(separation.y()))
+    }
+
+    pub fn wrap_bodies(&self, bodies: &mut [&mut Body]) {
+        for body in bodies {
+            let wrapped = self.wrap(body.pos());
+            body.translate(wrapped - body.pos().clone());
+        }
+    }
+
+    // Copy of `other` moved to its image nearest to `body`.
+    pub(crate) fn nearest_body(&self, body: &Body, other: &Body) -> Body {
+        let separation = other.pos().clone() - body.pos().clone();
+        let mut image = other.clone();
+        image.translate(self.nearest_image(&separation) - separation);
+
+        image
+    }
+}
+
+/// Applies a force law to the nearest image of each body only, which is
+/// enough for laws that fall off well within half the box.
+#[derive(PartialEq, Debug, Clone)]
+pub struct MinimumImage<L: ForceLaw> {
+    law: L,
+    periodic_box: PeriodicBox,
+}
+
+impl<L: ForceLaw> MinimumImage<L> {
+    pub fn new(law: L, periodic_box: PeriodicBox) -> MinimumImage<L> {
+        MinimumImage { law, periodic_box }
+    }
+}
+
+impl<L: ForceLaw> ForceLaw for MinimumImage<L> {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        self.law
+            .interact(body, &self.periodic_box.nearest_body(body, other))
+    }
+}
+
+impl<L: MultipoleExpansion> MultipoleExpansion for MinimumImage<L> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::force_law::Newtonian;
+
+    #[test]
+    fn wraps_positions_into_box() {
+        let periodic_box = PeriodicBox::new(Vec2::new(1.0, 0.0), 4.0);
+
+        assert_eq!(periodic_box.wrap(&Vec2::new(0.5, 1.5)), Vec2::new(0.5, 1.5));
+        assert_eq!(
+            periodic_box.wrap(&Vec2::new(3.5, -2.5)),
+            Vec2::new(-0.5, 1.5)
+        );
+        assert_eq!(
+            periodic_box.wrap(&Vec2::new(-9.0, 10.5)),
+            Vec2::new(-1.0, -1.5)
+        );
+
+        let mut body = Body::new(Vec2::new(4.0, 0.0), Vec2::unit(), 1.0);
+        periodic_box.wrap_bodies(&mut [&mut body]);
+        assert_eq!(body.pos(), &Vec2::new(0.0, 0.0));
+        assert_eq!(body.velocity(), &Vec2::unit());
+    }
+
+    #[test]
+    fn finds_nearest_image() {
+        let periodic_box = PeriodicBox::new(Vec2::zero(), 4.0);
+
+        assert_eq!(
+            periodic_box.nearest_image(&Vec2::new(1.5, -1.0)),
+            Vec2::new(1.5, -1.0)
+        );
+        assert_eq!(
+            periodic_box.nearest_image(&Vec2::new(3.0, -2.5)),
+            Vec2::new(-1.0, 1.5)
+        );
+        assert_eq!(
+            periodic_box.nearest_image(&Vec2::new(-7.0, 9.0)),
+            Vec2::new(1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn interacts_with_nearest_image() {
+        let law = MinimumImage::new(Newtonian::new(1.0), PeriodicBox::new(Vec2::zero(), 4.0));
+
+        let body = Body::new(Vec2::new(-1.5, 0.0), Vec2::zero(), 1.0);
+        let other = Body::new(Vec2::new(1.5, 0.0), Vec2::zero(), 1.0);
+
+        assert_eq!(law.interact(&body, &other), (Vec2::new(-1.0, 0.0), -1.0));
+    }
+}
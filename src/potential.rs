@@ -0,0 +1,381 @@
+use crate::vec2::Vec2;
+
+/// Analytic background potential acting on bodies in addition to their
+/// mutual forces.
+///
+/// Potentials are per unit mass and may depend on the simulation time.
+pub trait ExternalPotential {
+    fn potential(&self, pos: &Vec2, time: f64) -> f64;
+
+    fn acceleration(&self, pos: &Vec2, time: f64) -> Vec2;
+
+    /// Speed of a circular orbit through `pos`, zero where the potential
+    /// pushes outwards.
+    fn circular_velocity(&self, pos: &Vec2, time: f64) -> f64 {
+        (-self.acceleration(pos, time).dot(pos)).max(0.0).sqrt()
+    }
+
+    fn plus<P: ExternalPotential>(self, other: P) -> Sum<Self, P>
+    where
+        Self: Sized,
+    {
+        Sum(self, other)
+    }
+}
+
+/// Sum of two potentials, built with `ExternalPotential::plus`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Sum<A, B>(A, B);
+
+impl<A: ExternalPotential, B: ExternalPotential> ExternalPotential for Sum<A, B> {
+    fn potential(&self, pos: &Vec2, time: f64) -> f64 {
+        self.0.potential(pos, time) + self.1.potential(pos, time)
+    }
+
+    fn acceleration(&self, pos: &Vec2, time: f64) -> Vec2 {
+        self.0.acceleration(pos, time) + self.1.acceleration(pos, time)
+    }
+}
+
+impl ExternalPotential for Vec<Box<dyn ExternalPotential>> {
+    fn potential(&self, pos: &Vec2, time: f64) -> f64 {
+        self.iter().map(|p| p.potential(pos, time)).sum()
+    }
+
+    fn acceleration(&self, pos: &Vec2, time: f64) -> Vec2 {
+        self.iter()
+            .fold(Vec2::zero(), |acc, p| acc + p.acceleration(pos, time))
+    }
+}
+
+// Acceleration of a spherical potential with radial derivative `slope`.
+fn radial(pos: &Vec2, r: f64, slope: f64) -> Vec2 {
+    if r == 0.0 {
+        return Vec2::zero();
+    }
+
+    pos.clone() * (-slope / r)
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct PointMass {
+    pos: Vec2,
+    mass: f64,
+    g: f64,
+}
+
+impl PointMass {
+    pub fn new(pos: Vec2, mass: f64, g: f64) -> PointMass {
+        PointMass { pos, mass, g }
+    }
+}
+
+impl ExternalPotential for PointMass {
+    fn potential(&self, pos: &Vec2, _time: f64) -> f64 {
+        -self.g * self.mass / pos.dist(&self.pos)
+    }
+
+    fn acceleration(&self, pos: &Vec2, _time: f64) -> Vec2 {
+        let diff = pos.clone() - self.pos.clone();
+        let r = diff.length();
+
+        radial(&diff, r, self.g * self.mass / r.powi(2))
+    }
+}
+
+/// Logarithmic potential with a flat rotation curve at `velocity` outside
+/// `core_radius`, optionally flattened along y.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Logarithmic {
+    velocity: f64,
+    core_radius: f64,
+    flattening: f64,
+}
+
+impl Logarithmic {
+    pub fn new(velocity: f64, core_radius: f64) -> Logarithmic {
+        Logarithmic {
+            velocity,
+            core_radius,
+            flattening: 1.0,
+        }
+    }
+
+    pub fn with_flattening(mut self, flattening: f64) -> Logarithmic {
+        self.flattening = flattening;
+        self
+    }
+
+    fn denominator(&self, pos: &Vec2) -> f64 {
+        self.core_radius.powi(2) + pos.x().powi(2) + (pos.y() / self.flattening).powi(2)
+    }
+}
+
+impl ExternalPotential for Logarithmic {
+    fn potential(&self, pos: &Vec2, _time: f64) -> f64 {
+        0.5 * self.velocity.powi(2) * self.denominator(pos).ln()
+    }
+
+    fn acceleration(&self, pos: &Vec2, _time: f64) -> Vec2 {
+        let scale = -self.velocity.powi(2) / self.denominator(pos);
+
+        Vec2::new(pos.x() * scale, pos.y() * scale / self.flattening.powi(2))
+    }
+}
+
+/// Navarro-Frenk-White halo, where `mass` is `4π ρ0 rs³`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Nfw {
+    mass: f64,
+    scale_radius: f64,
+    g: f64,
+}
+
+impl Nfw {
+    pub fn new(mass: f64, scale_radius: f64, g: f64) -> Nfw {
+        Nfw {
+            mass,
+            scale_radius,
+            g,
+        }
+    }
+}
+
+impl ExternalPotential for Nfw {
+    fn potential(&self, pos: &Vec2, _time: f64) -> f64 {
+        let r = pos.length();
+        if r == 0.0 {
+            return -self.g * self.mass / self.scale_radius;
+        }
+
+        -self.g * self.mass * (r / self.scale_radius).ln_1p() / r
+    }
+
+    fn acceleration(&self, pos: &Vec2, _time: f64) -> Vec2 {
+        let r = pos.length();
+        let slope = self.g
+            * self.mass
+            * ((r / self.scale_radius).ln_1p() / r.powi(2) - 1.0 / (r * (self.scale_radius + r)));
+
+        radial(pos, r, slope)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Hernquist {
+    mass: f64,
+    scale_radius: f64,
+    g: f64,
+}
+
+impl Hernquist {
+    pub fn new(mass: f64, scale_radius: f64, g: f64) -> Hernquist {
+        Hernquist {
+            mass,
+            scale_radius,
+            g,
+        }
+    }
+}
+
+impl ExternalPotential for Hernquist {
+    fn potential(&self, pos: &Vec2, _time: f64) -> f64 {
+        -self.g * self.mass / (pos.length() + self.scale_radius)
+    }
+
+    fn acceleration(&self, pos: &Vec2, _time: f64) -> Vec2 {
+        let r = pos.length();
+
+        radial(pos, r, self.g * self.mass / (r + self.scale_radius).powi(2))
+    }
+}
+
+/// Miyamoto-Nagai disk evaluated in its midplane, where it reduces to a
+/// Plummer sphere with scale `a + b`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct MiyamotoNagai {
+    mass: f64,
+    a: f64,
+    b: f64,
+    g: f64,
+}
+
+impl MiyamotoNagai {
+    pub fn new(mass: f64, a: f64, b: f64, g: f64) -> MiyamotoNagai {
+        MiyamotoNagai { mass, a, b, g }
+    }
+
+    fn squared_distance(&self, pos: &Vec2) -> f64 {
+        pos.dot(pos) + (self.a + self.b).powi(2)
+    }
+}
+
+impl ExternalPotential for MiyamotoNagai {
+    fn potential(&self, pos: &Vec2, _time: f64) -> f64 {
+        -self.g * self.mass / self.squared_distance(pos).sqrt()
+    }
+
+    fn acceleration(&self, pos: &Vec2, _time: f64) -> Vec2 {
+        pos.clone() * (-self.g * self.mass / self.squared_distance(pos).powf(1.5))
+    }
+}
+
+/// Quadrupole bar `-A (X² - Y²) / Rb² / (1 + R²/Rb²)^(5/2)` in a frame
+/// rotating at `pattern_speed`, falling off as `R^-3` outside `radius`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RotatingBar {
+    strength: f64,
+    radius: f64,
+    pattern_speed: f64,
+    angle: f64,
+}
+
+impl RotatingBar {
+    pub fn new(strength: f64, radius: f64, pattern_speed: f64) -> RotatingBar {
+        RotatingBar {
+            strength,
+            radius,
+            pattern_speed,
+            angle: 0.0,
+        }
+    }
+
+    /// Angle of the bar's major axis at time zero.
+    pub fn with_angle(mut self, angle: f64) -> RotatingBar {
+        self.angle = angle;
+        self
+    }
+
+    pub fn angle(&self, time: f64) -> f64 {
+        self.angle + self.pattern_speed * time
+    }
+}
+
+impl ExternalPotential for RotatingBar {
+    fn potential(&self, pos: &Vec2, time: f64) -> f64 {
+        let bar = pos.rotate(-self.angle(time));
+        let s = 1.0 + bar.dot(&bar) / self.radius.powi(2);
+
+        -self.strength * (bar.x().powi(2) - bar.y().powi(2)) / self.radius.powi(2) / s.powf(2.5)
+    }
+
+    fn acceleration(&self, pos: &Vec2, time: f64) -> Vec2 {
+        let angle = self.angle(time);
+        let bar = pos.rotate(-angle);
+        let radius_squared = self.radius.powi(2);
+        let s = 1.0 + bar.dot(&bar) / radius_squared;
+        let q = (bar.x().powi(2) - bar.y().powi(2)) / radius_squared;
+
+        let scale = 2.0 * self.strength / radius_squared / s.powf(3.5);
+        Vec2::new(
+            bar.x() * scale * (s - 2.5 * q),
+            bar.y() * scale * (-s - 2.5 * q),
+        )
+        .rotate(angle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_conservative<P: ExternalPotential>(potential: &P, pos: Vec2, time: f64) {
+        let h = 1e-6 * pos.length();
+        let dx = Vec2::new(h, 0.0);
+        let dy = Vec2::new(0.0, h);
+
+        let gradient = Vec2::new(
+            (potential.potential(&(pos.clone() + dx.clone()), time)
+                - potential.potential(&(pos.clone() - dx), time))
+                / (2.0 * h),
+            (potential.potential(&(pos.clone() + dy.clone()), time)
+                - potential.potential(&(pos.clone() - dy), time))
+                / (2.0 * h),
+        );
+        let acceleration = potential.acceleration(&pos, time);
+
+        assert!(
+            (acceleration.clone() + gradient.clone()).length() < 1e-6 * gradient.length(),
+            "{:?} != {:?}",
+            acceleration,
+            gradient * -1.0
+        );
+    }
+
+    #[test]
+    fn accelerates_down_the_potential_gradient() {
+        let positions = [
+            Vec2::new(0.3, 0.1),
+            Vec2::new(-2.0, 1.5),
+            Vec2::new(4.0, -7.0),
+        ];
+
+        for pos in positions.iter() {
+            assert_conservative(
+                &PointMass::new(Vec2::new(1.0, 1.0), 3.0, 2.0),
+                pos.clone(),
+                0.0,
+            );
+            assert_conservative(
+                &Logarithmic::new(1.5, 0.5).with_flattening(0.8),
+                pos.clone(),
+                0.0,
+            );
+            assert_conservative(&Nfw::new(3.0, 2.0, 1.0), pos.clone(), 0.0);
+            assert_conservative(&Hernquist::new(3.0, 2.0, 1.0), pos.clone(), 0.0);
+            assert_conservative(&MiyamotoNagai::new(3.0, 1.0, 0.2, 1.0), pos.clone(), 0.0);
+            assert_conservative(
+                &RotatingBar::new(0.5, 2.0, 0.3).with_angle(0.4),
+                pos.clone(),
+                1.7,
+            );
+        }
+    }
+
+    #[test]
+    fn has_expected_circular_velocities() {
+        let far = Vec2::new(0.0, 1e4);
+
+        assert!((Logarithmic::new(2.0, 1.0).circular_velocity(&far, 0.0) - 2.0).abs() < 1e-6);
+        assert!(
+            (PointMass::new(Vec2::zero(), 4.0, 1.0).circular_velocity(&Vec2::new(4.0, 0.0), 0.0)
+                - 1.0)
+                .abs()
+                < 1e-12
+        );
+        assert_eq!(
+            Nfw::new(1.0, 1.0, 1.0).acceleration(&Vec2::zero(), 0.0),
+            Vec2::zero()
+        );
+    }
+
+    #[test]
+    fn sums_potentials() {
+        let pos = Vec2::new(1.0, 2.0);
+        let halo = Hernquist::new(3.0, 2.0, 1.0);
+        let disk = MiyamotoNagai::new(1.0, 1.0, 0.2, 1.0);
+
+        let sum = halo.clone().plus(disk.clone());
+        assert_eq!(
+            sum.potential(&pos, 0.0),
+            halo.potential(&pos, 0.0) + disk.potential(&pos, 0.0)
+        );
+
+        let boxed: Vec<Box<dyn ExternalPotential>> = vec![Box::new(halo), Box::new(disk)];
+        assert_eq!(boxed.acceleration(&pos, 0.0), sum.acceleration(&pos, 0.0));
+    }
+
+    #[test]
+    fn rotates_bar_with_pattern_speed() {
+        let bar = RotatingBar::new(1.0, 1.0, 0.5);
+        let pos = Vec2::new(0.7, 0.2);
+
+        let quarter_turn = std::f64::consts::PI / 2.0 / 0.5;
+        let rotated = pos.rotate(std::f64::consts::PI / 2.0);
+
+        assert!((bar.potential(&rotated, quarter_turn) - bar.potential(&pos, 0.0)).abs() < 1e-12);
+        assert!(
+            bar.potential(&Vec2::new(1.0, 0.0), 0.0) < bar.potential(&Vec2::new(0.0, 1.0), 0.0)
+        );
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    body::Body, ic::to_center_of_mass_frame, kepler::OrbitalElements, units::UnitSystem, vec2::Vec2,
+};
+
+/// Period of the figure-eight orbit in N-body units.
+pub const FIGURE_EIGHT_PERIOD: f64 = 6.325_913_985;
+
+// Mass (solar masses), semi-major axis (AU), eccentricity, longitude of
+// perihelion and mean longitude (degrees) at J2000, from Standish's
+// approximate elements.
+const INNER_PLANETS: [(f64, f64, f64, f64, f64); 4] = [
+    (1.6597e-7, 0.38709927, 0.20563593, 77.45779628, 252.25032350),
+    (
+        2.4473e-6,
+        0.72333566,
+        0.00677672,
+        131.60246718,
+        181.97909950,
+    ),
+    (
+        3.0398e-6,
+        1.00000261,
+        0.01671123,
+        102.93768193,
+        100.46457166,
+    ),
+    (3.2264e-7, 1.52371034, 0.09339410, -23.94362959, -4.55343205),
+];
+const OUTER_PLANETS: [(f64, f64, f64, f64, f64); 4] = [
+    (9.5440e-4, 5.20288700, 0.04838624, 14.72847983, 34.39644051),
+    (2.8575e-4, 9.53667594, 0.05386179, 92.59887831, 49.95424423),
+    (
+        4.3647e-5,
+        19.18916464,
+        0.04725744,
+        170.95427630,
+        313.23810451,
+    ),
+    (
+        5.1492e-5,
+        30.06992276,
+        0.00859048,
+        44.96476227,
+        -55.12002969,
+    ),
+];
+
+/// The Sun followed by Mercury, Venus, the Earth-Moon barycenter and Mars.
+pub fn inner_solar_system(units: &UnitSystem) -> Vec<Body> {
+    solar_system_with(&INNER_PLANETS, units)
+}
+
+/// The Sun followed by Jupiter, Saturn, Uranus and Neptune.
+pub fn outer_solar_system(units: &UnitSystem) -> Vec<Body> {
+    solar_system_with(&OUTER_PLANETS, units)
+}
+
+pub fn solar_system(units: &UnitSystem) -> Vec<Body> {
+    let mut planets = INNER_PLANETS.to_vec();
+    planets.extend_from_slice(&OUTER_PLANETS);
+
+    solar_system_with(&planets, units)
+}
+
+fn solar_system_with(planets: &[(f64, f64, f64, f64, f64)], units: &UnitSystem) -> Vec<Body> {
+    let astronomical = UnitSystem::astronomical();
+    let sun = Body::new(Vec2::zero(), Vec2::zero(), 1.0);
+
+    let mut bodies = vec![sun.clone()];
+    for &(mass, semi_major_axis, eccentricity, perihelion, mean_longitude) in planets {
+        bodies.push(
+            OrbitalElements::new(
+                semi_major_axis,
+                eccentricity,
+                perihelion.to_radians(),
+                (mean_longitude - perihelion).to_radians(),
+            )
+            .to_body(&sun, mass, astronomical.gravitational_constant()),
+        );
+    }
+
+    to_center_of_mass_frame(&mut bodies);
+    astronomical.convert_bodies(&bodies, units)
+}
+
+/// Chenciner-Montgomery figure-eight of three unit masses, in N-body units.
+pub fn figure_eight() -> Vec<Body> {
+    let pos = Vec2::new(0.97000436, -0.24308753);
+    let velocity = Vec2::new(-0.93240737, -0.86473146);
+
+    vec![
+        Body::new(pos.clone(), velocity.clone() * -0.5, 1.0),
+        Body::new(pos * -1.0, velocity.clone() * -0.5, 1.0),
+        Body::new(Vec2::zero(), velocity, 1.0),
+    ]
+}
+
+/// Lagrange's equilateral solution: three bodies at the corners of a
+/// triangle with side `side`, rotating rigidly around their center of mass.
+pub fn lagrange_triangle(masses: [f64; 3], side: f64, g: f64) -> Vec<Body> {
+    let total_mass: f64 = masses.iter().sum();
+    let angular_velocity = (g * total_mass / side.powi(3)).sqrt();
+
+    let mut bodies: Vec<Body> = masses
+        .iter()
+        .enumerate()
+        .map(|(i, &mass)| {
+            let angle = 2.0 * std::f64::consts::PI * i as f64 / 3.0;
+            Body::new(
+                Vec2::new(angle.cos(), angle.sin()) * (side / 3f64.sqrt()),
+                Vec2::zero(),
+                mass,
+            )
+        })
+        .collect();
+    to_center_of_mass_frame(&mut bodies);
+
+    for body in &mut bodies {
+        let pos = body.pos().clone();
+        body.set_velocity(Vec2::new(-pos.y(), pos.x()) * angular_velocity);
+    }
+
+    bodies
+}
+
+/// Burrau's problem: bodies of mass 3, 4 and 5 at rest on the corners of a
+/// 3-4-5 right triangle, in N-body units.
+pub fn pythagorean() -> Vec<Body> {
+    vec![
+        Body::new(Vec2::new(1.0, 3.0), Vec2::zero(), 3.0),
+        Body::new(Vec2::new(-2.0, -1.0), Vec2::zero(), 4.0),
+        Body::new(Vec2::new(1.0, -1.0), Vec2::zero(), 5.0),
+    ]
+}
+
+/// Two stars on a circular orbit with `separation`, followed by planets
+/// orbiting the center of mass of the pair.
+pub fn binary_with_planets(
+    star_masses: [f64; 2],
+    separation: f64,
+    planets: &[(f64, OrbitalElements)],
+    g: f64,
+) -> Vec<Body> {
+    let [primary_mass, secondary_mass] = star_masses;
+    let primary = Body::new(Vec2::zero(), Vec2::zero(), primary_mass);
+
+    let mut bodies = vec![
+        primary.clone(),
+        OrbitalElements::new(separation, 0.0, 0.0, 0.0).to_body(&primary, secondary_mass, g),
+    ];
+    to_center_of_mass_frame(&mut bodies);
+
+    let binary = Body::new(Vec2::zero(), Vec2::zero(), primary_mass + secondary_mass);
+    for (mass, elements) in planets {
+        bodies.push(elements.to_body(&binary, *mass, g));
+    }
+
+    to_center_of_mass_frame(&mut bodies);
+    bodies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ic::{center_of_mass, potential_energy},
+        simulation::{brute_force::BruteForce, Simulation},
+        units::{AU, G, YEAR},
+    };
+
+    fn run(bodies: &mut [Body], g: f64, time: f64, steps: usize) {
+        let mut simulation = BruteForce::new(g);
+        for _ in 0..steps {
+            simulation.step(&mut bodies.iter_mut().collect(), time / steps as f64);
+        }
+    }
+
+    #[test]
+    fn builds_solar_system_from_orbital_elements() {
+        let si = UnitSystem::si();
+        let bodies = solar_system(&si);
+        assert_eq!(bodies.len(), 9);
+
+        let (pos, velocity) = center_of_mass(&bodies);
+        assert!(pos.length() < 1.0);
+        assert!(velocity.length() < 1e-6);
+
+        let astronomical = UnitSystem::astronomical();
+        let planets = INNER_PLANETS.iter().chain(OUTER_PLANETS.iter());
+        for (body, &(mass, semi_major_axis, eccentricity, _, _)) in bodies[1..].iter().zip(planets)
+        {
+            let elements = OrbitalElements::from_state(&bodies[0], body, G);
+
+            assert!((si.convert_mass(body.mass(), &astronomical) - mass).abs() < 1e-15);
+            assert!(
+                (si.convert_length(elements.semi_major_axis(), &astronomical) - semi_major_axis)
+                    .abs()
+                    < 1e-9
+            );
+            assert!((elements.eccentricity() - eccentricity).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn keeps_earth_orbit_for_a_year() {
+        let astronomical = UnitSystem::astronomical();
+        let g = astronomical.gravitational_constant();
+        let mut bodies = inner_solar_system(&astronomical);
+
+        run(&mut bodies, g, 1.0, 20_000);
+
+        let earth = OrbitalElements::from_state(&bodies[0], &bodies[3], g);
+        assert!((earth.semi_major_axis() - 1.0).abs() < 1e-3);
+        assert!((earth.eccentricity() - 0.0167).abs() < 1e-3);
+    }
+
+    #[test]
+    fn converts_solar_system_between_units() {
+        let si = solar_system(&UnitSystem::si());
+        let astronomical = solar_system(&UnitSystem::astronomical());
+
+        let earth_speed = si[3].velocity().length();
+        assert!(
+            (astronomical[3].velocity().length() * AU / YEAR - earth_speed).abs()
+                < 1e-6 * earth_speed
+        );
+    }
+
+    #[test]
+    fn returns_figure_eight_to_initial_positions_after_a_period() {
+        let initial_bodies = figure_eight();
+        let mut bodies = initial_bodies.clone();
+
+        run(&mut bodies, 1.0, FIGURE_EIGHT_PERIOD, 20_000);
+
+        for (body, initial_body) in bodies.iter().zip(&initial_bodies) {
+            assert!(body.pos().dist(initial_body.pos()) < 1e-2);
+        }
+    }
+
+    #[test]
+    fn keeps_lagrange_triangle_equilateral() {
+        let side = 1e11;
+        let mut bodies = lagrange_triangle([1e30, 2e30, 3e30], side, G);
+
+        let period = 2.0 * std::f64::consts::PI * (side.powi(3) / (G * 6e30)).sqrt();
+        run(&mut bodies, G, period / 4.0, 5_000);
+
+        for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+            assert!((bodies[i].pos().dist(bodies[j].pos()) - side).abs() < 1e-3 * side);
+        }
+    }
+
+    #[test]
+    fn starts_pythagorean_problem_at_rest() {
+        let bodies = pythagorean();
+
+        assert!(bodies.iter().all(|b| b.velocity() == &Vec2::zero()));
+        assert_eq!(center_of_mass(&bodies).0, Vec2::zero());
+        assert!((potential_energy(&bodies, 1.0) + 769.0 / 60.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn orbits_planets_around_binary() {
+        let bodies = binary_with_planets(
+            [2e30, 1e30],
+            1e10,
+            &[(6e24, OrbitalElements::new(1e11, 0.1, 1.0, 2.0))],
+            G,
+        );
+        assert_eq!(bodies.len(), 3);
+        assert!((bodies[0].pos().dist(bodies[1].pos()) - 1e10).abs() < 1e-3);
+
+        let (pos, velocity) = center_of_mass(&bodies[..2]);
+        let elements = OrbitalElements::from_state(&Body::new(pos, velocity, 3e30), &bodies[2], G);
+
+        assert!((elements.semi_major_axis() - 1e11).abs() < 1e-3);
+        assert!((elements.eccentricity() - 0.1).abs() < 1e-12);
+    }
+}
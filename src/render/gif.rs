@@ -0,0 +1,400 @@
+use super::Canvas;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+const MAX_CODE_WIDTH: u32 = 12;
+
+/// Writes canvases as frames of a looping animated GIF. Frames are scaled
+/// to the size of the animation and given their own palette, exact when
+/// they hold at most 256 colors and 3-3-2 bit RGB otherwise.
+pub struct GifEncoder<W: Write> {
+    writer: W,
+    width: usize,
+    height: usize,
+    delay: u16,
+    frame_skip: usize,
+    frames_seen: usize,
+    started: bool,
+}
+
+impl<W: Write> GifEncoder<W> {
+    pub fn new(writer: W, width: usize, height: usize) -> GifEncoder<W> {
+        assert!(width <= u16::MAX as usize && height <= u16::MAX as usize);
+
+        GifEncoder {
+            writer,
+            width,
+            height,
+            delay: 4,
+            frame_skip: 0,
+            frames_seen: 0,
+            started: false,
+        }
+    }
+
+    /// Frames shown per second, 25 by default. GIF delays come in
+    /// hundredths of a second, so the rate is rounded to match.
+    pub fn with_frame_rate(mut self, frame_rate: f64) -> GifEncoder<W> {
+        self.delay = (100.0 / frame_rate).round().max(1.0) as u16;
+        self
+    }
+
+    /// Keeps one frame out of every `frame_skip + 1` given.
+    pub fn with_frame_skip(mut self, frame_skip: usize) -> GifEncoder<W> {
+        self.frame_skip = frame_skip;
+        self
+    }
+
+    pub fn add_frame(&mut self, canvas: &Canvas) -> io::Result<()> {
+        let skipped = !self.frames_seen.is_multiple_of(self.frame_skip + 1);
+        self.frames_seen += 1;
+        if skipped {
+            return Ok(());
+        }
+
+        self.start()?;
+
+        let canvas = if canvas.width() == self.width && canvas.height() == self.height {
+            canvas.clone()
+        } else {
+            canvas.resized(self.width, self.height)
+        };
+        let (palette, indices) = quantize(&canvas);
+
+        // Graphic control extension: keep the frame in place afterwards,
+        // no transparency.
+        self.writer.write_all(&[0x21, 0xf9, 4, 0x04])?;
+        self.writer.write_all(&self.delay.to_le_bytes())?;
+        self.writer.write_all(&[0, 0])?;
+
+        // Image descriptor covering the screen with a 256 color local table.
+        self.writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.writer.write_all(&(self.width as u16).to_le_bytes())?;
+        self.writer.write_all(&(self.height as u16).to_le_bytes())?;
+        self.writer.write_all(&[0x87])?;
+        self.writer.write_all(&palette)?;
+
+        self.writer.write_all(&[8])?;
+        for block in lzw_encode(&indices, 8).chunks(255) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+        self.writer.write_all(&[0])
+    }
+
+    /// Ends the animation and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start()?;
+        self.writer.write_all(&[0x3b])?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+
+        self.writer.write_all(b"GIF89a")?;
+        self.writer.write_all(&(self.width as u16).to_le_bytes())?;
+        self.writer.write_all(&(self.height as u16).to_le_bytes())?;
+        // No global color table, background color and aspect ratio unset.
+        self.writer.write_all(&[0, 0, 0])?;
+
+        // Netscape extension looping forever.
+        self.writer.write_all(&[0x21, 0xff, 11])?;
+        self.writer.write_all(b"NETSCAPE2.0")?;
+        self.writer.write_all(&[3, 1, 0, 0, 0])
+    }
+}
+
+// 256 color palette, padded with black, and the palette index of each
+// pixel. Alpha is dropped.
+fn quantize(canvas: &Canvas) -> (Vec<u8>, Vec<u8>) {
+    let mut colors: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut palette = Vec::with_capacity(256 * 3);
+    let mut indices = Vec::with_capacity(canvas.width() * canvas.height());
+
+    for pixel in canvas.pixels().chunks(4) {
+        let color = [pixel[0], pixel[1], pixel[2]];
+        let next = colors.len();
+        if next == 256 && !colors.contains_key(&color) {
+            return quantize_332(canvas);
+        }
+
+        let index = *colors.entry(color).or_insert_with(|| {
+            palette.extend_from_slice(&color);
+            next as u8
+        });
+        indices.push(index);
+    }
+
+    palette.resize(256 * 3, 0);
+    (palette, indices)
+}
+
+fn quantize_332(canvas: &Canvas) -> (Vec<u8>, Vec<u8>) {
+    let level = |value: u8, bits: u32| {
+        let max = (1 << bits) - 1;
+        (value as u32 * max + 127) / 255
+    };
+
+    let mut palette = Vec::with_capacity(256 * 3);
+    for index in 0..256u32 {
+        let (r, g, b) = (index >> 5, (index >> 2) & 7, index & 3);
+        palette.extend_from_slice(&[
+            (r * 255 / 7) as u8,
+            (g * 255 / 7) as u8,
+            (b * 255 / 3) as u8,
+        ]);
+    }
+
+    let indices = canvas
+        .pixels()
+        .chunks(4)
+        .map(|pixel| (level(pixel[0], 3) << 5 | level(pixel[1], 3) << 2 | level(pixel[2], 2)) as u8)
+        .collect();
+
+    (palette, indices)
+}
+
+/// Compresses `data`, symbols of `min_code_width` bits, into the variable
+/// width LZW codes GIF uses, packed from the least significant bit.
+pub fn lzw_encode(data: &[u8], min_code_width: u32) -> Vec<u8> {
+    let mut encoder = LzwEncoder::new(min_code_width);
+    encoder.output.write(encoder.clear, encoder.width);
+
+    let mut symbols = data.iter();
+    if let Some(&first) = symbols.next() {
+        let mut prefix = first as u16;
+        for &symbol in symbols {
+            if let Some(&code) = encoder.table.get(&(prefix, symbol)) {
+                prefix = code;
+                continue;
+            }
+
+            encoder.output.write(prefix, encoder.width);
+            if encoder.next_code() {
+                encoder.table.insert((prefix, symbol), encoder.last);
+            }
+            prefix = symbol as u16;
+        }
+
+        encoder.output.write(prefix, encoder.width);
+        encoder.next_code();
+    }
+
+    encoder.output.write(encoder.clear + 1, encoder.width);
+    encoder.output.finish()
+}
+
+struct LzwEncoder {
+    min_code_width: u32,
+    clear: u16,
+    table: HashMap<(u16, u8), u16>,
+    width: u32,
+    // Last code given out, and the first one needing another bit.
+    last: u16,
+    overflow: u16,
+    output: BitWriter,
+}
+
+impl LzwEncoder {
+    fn new(min_code_width: u32) -> LzwEncoder {
+        let clear = 1 << min_code_width;
+
+        LzwEncoder {
+            min_code_width,
+            clear,
+            table: HashMap::new(),
+            width: min_code_width + 1,
+            last: clear + 1,
+            overflow: clear << 1,
+            output: BitWriter::default(),
+        }
+    }
+
+    // Takes the next code for a new table entry, widening codes as soon as
+    // it no longer fits since decoders add entries one code late. Returns
+    // false when the table was full and got cleared instead.
+    fn next_code(&mut self) -> bool {
+        self.last += 1;
+        if self.last == self.overflow {
+            self.width += 1;
+            self.overflow <<= 1;
+        }
+
+        if self.last == (1 << MAX_CODE_WIDTH) - 1 {
+            self.output.write(self.clear, self.width);
+            self.table.clear();
+            self.width = self.min_code_width + 1;
+            self.last = self.clear + 1;
+            self.overflow = self.clear << 1;
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Decodes GIF flavoured LZW the way viewers do.
+    fn lzw_decode(data: &[u8], min_code_width: u32) -> Vec<u8> {
+        let clear = 1usize << min_code_width;
+        let (mut buffer, mut bits, mut at) = (0u32, 0, 0);
+        let mut width = min_code_width + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut previous: Option<usize> = None;
+        let mut output = Vec::new();
+
+        loop {
+            while bits < width {
+                buffer |= (data[at] as u32) << bits;
+                at += 1;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << width) - 1)) as usize;
+            buffer >>= width;
+            bits -= width;
+
+            if code == clear {
+                table = (0..clear).map(|symbol| vec![symbol as u8]).collect();
+                table.push(Vec::new());
+                table.push(Vec::new());
+                width = min_code_width + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return output;
+            }
+
+            let entry = match (code < table.len(), previous) {
+                (true, _) => table[code].clone(),
+                (false, Some(previous)) => {
+                    let mut entry = table[previous].clone();
+                    entry.push(table[previous][0]);
+                    entry
+                }
+                (false, None) => panic!("unknown code {}", code),
+            };
+            if let Some(previous) = previous {
+                let mut new_entry = table[previous].clone();
+                new_entry.push(entry[0]);
+                table.push(new_entry);
+            }
+            if table.len() == 1 << width && width < MAX_CODE_WIDTH {
+                width += 1;
+            }
+
+            output.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn round_trips_lzw() {
+        assert_eq!(lzw_decode(&lzw_encode(&[], 8), 8), Vec::<u8>::new());
+        assert_eq!(lzw_decode(&lzw_encode(&[3], 2), 2), vec![3]);
+
+        let repetitive = vec![3; 100_000];
+        let encoded = lzw_encode(&repetitive, 8);
+        assert!(encoded.len() < 1000);
+        assert_eq!(lzw_decode(&encoded, 8), repetitive);
+
+        // Noisy data fills the table several times over.
+        let mut state = 1u32;
+        let noisy: Vec<u8> = (0..200_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8 % 5
+            })
+            .collect();
+        assert_eq!(lzw_decode(&lzw_encode(&noisy, 8), 8), noisy);
+        let symbols: Vec<u8> = noisy.iter().map(|symbol| symbol % 4).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&symbols, 2), 2), symbols);
+    }
+
+    #[test]
+    fn uses_exact_palette_when_possible() {
+        let mut canvas = Canvas::new(4, 1);
+        canvas.clear([0, 0, 0, 255]);
+        canvas.fill_circle(0.5, 0.5, 0.5, [200, 10, 10, 255]);
+
+        let (palette, indices) = quantize(&canvas);
+        assert_eq!(&palette[..6], &[200, 10, 10, 0, 0, 0]);
+        assert_eq!(indices, vec![0, 1, 1, 1]);
+
+        // A gradient with more than 256 colors falls back to 3-3-2 bits.
+        let mut canvas = Canvas::new(300, 1);
+        for x in 0..300 {
+            canvas.fill_circle(
+                x as f64 + 0.5,
+                0.5,
+                0.5,
+                [(x % 256) as u8, (x / 256) as u8, 0, 255],
+            );
+        }
+        let (palette, indices) = quantize(&canvas);
+        assert_eq!(palette.len(), 768);
+        assert_eq!(indices[0], 0);
+        assert_eq!(indices[255], 0b1110_0000);
+    }
+
+    #[test]
+    fn writes_animation() {
+        let mut canvas = Canvas::new(8, 8);
+        let mut encoder = GifEncoder::new(Vec::new(), 4, 4)
+            .with_frame_rate(10.0)
+            .with_frame_skip(1);
+        for frame in 0..5 {
+            canvas.clear([frame * 50, 0, 0, 255]);
+            encoder.add_frame(&canvas).unwrap();
+        }
+        let gif = encoder.finish().unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], &[4, 0, 4, 0]);
+        assert_eq!(*gif.last().unwrap(), 0x3b);
+
+        // Frames 0, 2 and 4, each shown for a tenth of a second.
+        let control = [0x21, 0xf9, 4, 0x04, 10, 0];
+        let frames = gif.windows(control.len()).filter(|w| *w == control).count();
+        assert_eq!(frames, 3);
+        let descriptor = [0x2c, 0, 0, 0, 0, 4, 0, 4, 0, 0x87, 200, 0, 0];
+        assert!(gif.windows(descriptor.len()).any(|w| w == descriptor));
+    }
+}
@@ -0,0 +1,264 @@
+pub mod gif;
+pub mod png;
+
+use crate::{body::Body, vec2::Vec2};
+
+/// Red, green, blue and alpha channels of a pixel.
+pub type Color = [u8; 4];
+
+pub const BLACK: Color = [0, 0, 0, 255];
+
+/// Maps positions to pixels: `center` lands in the middle of the image and
+/// `extent` spans its width and height, with y growing downwards.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Camera {
+    center: Vec2,
+    extent: f64,
+}
+
+impl Camera {
+    pub fn new(center: Vec2, extent: f64) -> Camera {
+        Camera { center, extent }
+    }
+
+    pub fn center(&self) -> &Vec2 {
+        &self.center
+    }
+
+    pub fn extent(&self) -> f64 {
+        self.extent
+    }
+
+    pub fn set_center(&mut self, center: Vec2) {
+        self.center = center;
+    }
+
+    pub fn to_screen(&self, pos: &Vec2, width: f64, height: f64) -> (f64, f64) {
+        (
+            (pos.x() - self.center.x()) * width / self.extent + width / 2.0,
+            (pos.y() - self.center.y()) * height / self.extent + height / 2.0,
+        )
+    }
+
+    pub fn to_world(&self, x: f64, y: f64, width: f64, height: f64) -> Vec2 {
+        Vec2::new(
+            (x - width / 2.0) * self.extent / width + self.center.x(),
+            (y - height / 2.0) * self.extent / height + self.center.y(),
+        )
+    }
+
+    /// Magnifies the view by `factor`, keeping the position under the pixel
+    /// `(x, y)` in place.
+    pub fn zoom(&mut self, factor: f64, x: f64, y: f64, width: f64, height: f64) {
+        let anchor = self.to_world(x, y, width, height);
+        self.extent /= factor;
+        let moved = self.to_world(x, y, width, height);
+
+        self.center = self.center.clone() + anchor - moved;
+    }
+
+    /// Follows a drag of `(dx, dy)` pixels, moving the content with it.
+    pub fn pan(&mut self, dx: f64, dy: f64, width: f64, height: f64) {
+        self.center =
+            self.center.clone() - Vec2::new(dx * self.extent / width, dy * self.extent / height);
+    }
+
+    /// Centers the view on the bounding box of `positions`, leaving a
+    /// `margin` fraction of its size around it. The extent is kept when
+    /// there is nothing to fit.
+    pub fn fit<'a, I>(&mut self, positions: I, margin: f64)
+    where
+        I: IntoIterator<Item = &'a Vec2>,
+    {
+        let mut bounds: Option<(Vec2, Vec2)> = None;
+        for pos in positions {
+            bounds = Some(match bounds {
+                Some((min, max)) => (
+                    Vec2::new(min.x().min(pos.x()), min.y().min(pos.y())),
+                    Vec2::new(max.x().max(pos.x()), max.y().max(pos.y())),
+                ),
+                None => (pos.clone(), pos.clone()),
+            });
+        }
+
+        if let Some((min, max)) = bounds {
+            let size = (max.x() - min.x()).max(max.y() - min.y());
+            self.center = (min + max) / 2.0;
+            if size > 0.0 {
+                self.extent = size * (1.0 + margin);
+            }
+        }
+    }
+}
+
+/// RGBA image drawn on the CPU, rows from the top.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Channels of every pixel, row by row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let start = (y * self.width + x) * 4;
+        let mut color = [0; 4];
+        color.copy_from_slice(&self.pixels[start..start + 4]);
+        color
+    }
+
+    /// Copy scaled to `width` by `height`, taking the nearest pixel.
+    pub fn resized(&self, width: usize, height: usize) -> Canvas {
+        let mut resized = Canvas::new(width, height);
+        for y in 0..height {
+            let source_y = y * self.height / height;
+            for x in 0..width {
+                let source_x = x * self.width / width;
+                let start = (y * width + x) * 4;
+                resized.pixels[start..start + 4].copy_from_slice(&self.pixel(source_x, source_y));
+            }
+        }
+
+        resized
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        for pixel in self.pixels.chunks_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+    }
+
+    /// Blends a disk over the canvas, antialiased over one pixel at its edge.
+    pub fn fill_circle(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+        let reach = radius + 0.5;
+        let columns = pixel_range(x - reach, x + reach, self.width);
+        let rows = pixel_range(y - reach, y + reach, self.height);
+
+        for row in rows {
+            for column in columns.clone() {
+                let dist = (column as f64 + 0.5 - x).hypot(row as f64 + 0.5 - y);
+                let coverage = (reach - dist).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.blend(column, row, color, coverage);
+                }
+            }
+        }
+    }
+
+    /// Draws each body as a circle `radius` pixels wide seen from `camera`.
+    pub fn draw_bodies<'a, I>(&mut self, bodies: I, camera: &Camera, radius: f64)
+    where
+        I: IntoIterator<Item = (&'a Body, Color)>,
+    {
+        let (width, height) = (self.width as f64, self.height as f64);
+        for (body, color) in bodies {
+            let (x, y) = camera.to_screen(body.pos(), width, height);
+            self.fill_circle(x, y, radius, color);
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: Color, coverage: f64) {
+        let start = (y * self.width + x) * 4;
+        let pixel = &mut self.pixels[start..start + 4];
+        let alpha = color[3] as f64 / 255.0 * coverage;
+
+        for channel in 0..3 {
+            pixel[channel] = (pixel[channel] as f64 * (1.0 - alpha) + color[channel] as f64 * alpha)
+                .round() as u8;
+        }
+        pixel[3] = (pixel[3] as f64 + (255.0 - pixel[3] as f64) * alpha).round() as u8;
+    }
+}
+
+// Pixels overlapping `[from, to]`, clipped to `[0, size)`.
+fn pixel_range(from: f64, to: f64, size: usize) -> std::ops::Range<usize> {
+    let clip = |x: f64| x.clamp(0.0, size as f64) as usize;
+    clip(from.floor())..clip(to.ceil())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_positions_like_the_viewer() {
+        let camera = Camera::new(Vec2::new(1.0, -1.0), 4.0);
+
+        assert_eq!(
+            camera.to_screen(&Vec2::new(1.0, -1.0), 200.0, 100.0),
+            (100.0, 50.0)
+        );
+        assert_eq!(
+            camera.to_screen(&Vec2::new(3.0, 0.0), 200.0, 100.0),
+            (200.0, 75.0)
+        );
+    }
+
+    #[test]
+    fn zooms_pans_and_fits() {
+        let mut camera = Camera::new(Vec2::zero(), 4.0);
+        let anchor = camera.to_world(150.0, 25.0, 200.0, 100.0);
+        assert_eq!(anchor, Vec2::new(1.0, -1.0));
+
+        camera.zoom(2.0, 150.0, 25.0, 200.0, 100.0);
+        assert_eq!(camera.extent(), 2.0);
+        assert_eq!(camera.to_screen(&anchor, 200.0, 100.0), (150.0, 25.0));
+
+        camera.pan(100.0, -50.0, 200.0, 100.0);
+        assert_eq!(camera.to_screen(&anchor, 200.0, 100.0), (250.0, -25.0));
+
+        let positions = [
+            Vec2::new(-1.0, 2.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(1.0, 1.0),
+        ];
+        camera.fit(positions.iter(), 0.5);
+        assert_eq!(camera.center(), &Vec2::new(1.0, 1.0));
+        assert_eq!(camera.extent(), 6.0);
+
+        camera.fit(positions[..1].iter(), 0.5);
+        assert_eq!(camera.center(), &Vec2::new(-1.0, 2.0));
+        assert_eq!(camera.extent(), 6.0);
+    }
+
+    #[test]
+    fn draws_bodies_as_circles() {
+        let mut canvas = Canvas::new(20, 10);
+        canvas.clear(BLACK);
+
+        let body = Body::new(Vec2::new(-5.0, 0.0), Vec2::zero(), 1.0);
+        let red = [255, 0, 0, 255];
+        canvas.draw_bodies(vec![(&body, red)], &Camera::new(Vec2::zero(), 20.0), 2.0);
+
+        // Centered on the corner between pixels (4, 4) and (5, 5).
+        assert_eq!(canvas.pixel(4, 4), red);
+        assert_eq!(canvas.pixel(5, 5), red);
+        assert_eq!(canvas.pixel(0, 0), BLACK);
+        assert_eq!(canvas.pixel(12, 5), BLACK);
+
+        let edge = canvas.pixel(3, 5);
+        assert!(edge[0] > 0 && edge[0] < 255);
+        assert_eq!(edge[3], 255);
+    }
+}
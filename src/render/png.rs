@@ -0,0 +1,209 @@
+use super::Canvas;
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Longest block deflate can store uncompressed.
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+/// Writes `canvas` as an 8-bit RGBA PNG. Pixel data is stored without
+/// compression, so files take about four bytes per pixel.
+pub fn write_png<W: Write>(canvas: &Canvas, writer: &mut W) -> io::Result<()> {
+    writer.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(canvas.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(canvas.height() as u32).to_be_bytes());
+    // Bit depth 8, color type RGBA, default compression, filter and no
+    // interlacing.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(writer, b"IHDR", &header)?;
+
+    // Every scanline starts with filter type 0, none.
+    let row_length = canvas.width() * 4;
+    let mut scanlines = Vec::with_capacity((row_length + 1) * canvas.height());
+    for row in canvas.pixels().chunks(row_length.max(1)) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+    write_chunk(writer, b"IDAT", &zlib_stored(&scanlines))?;
+
+    write_chunk(writer, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    writer.write_all(&crc.finish().to_be_bytes())
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks.
+pub fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED_BLOCK);
+    let mut stream = Vec::with_capacity(data.len() + 5 * blocks.max(1) + 6);
+
+    // Deflate with a 32K window and no preset dictionary, with the check
+    // bits making the header a multiple of 31.
+    stream.extend_from_slice(&[0x78, 0x01]);
+
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let length = chunk.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// CRC-32 as used by PNG and zip, fed incrementally.
+#[derive(Debug, Clone)]
+pub struct Crc32 {
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        let mut table = [0; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 == 1 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+
+        Crc32 {
+            table,
+            crc: 0xffff_ffff,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.crc = self.table[((self.crc ^ byte as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        self.crc ^ 0xffff_ffff
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Crc32 {
+        Crc32::new()
+    }
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    // Largest run of bytes whose sums cannot overflow before reducing.
+    const RUN: usize = 5552;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for run in data.chunks(RUN) {
+        for &byte in run {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculates_checksums() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[0xff; 100_000]), 0x149a_302c);
+    }
+
+    // Reads back the payloads of uncompressed deflate blocks.
+    fn inflate_stored(stream: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut at = 2;
+        loop {
+            let last = stream[at] & 1 == 1;
+            let length = u16::from_le_bytes([stream[at + 1], stream[at + 2]]) as usize;
+            let complement = u16::from_le_bytes([stream[at + 3], stream[at + 4]]);
+            assert_eq!(!complement as usize, length);
+            data.extend_from_slice(&stream[at + 5..at + 5 + length]);
+            at += 5 + length;
+            if last {
+                break;
+            }
+        }
+
+        assert_eq!(&stream[at..], &adler32(&data).to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn stores_data_in_zlib_blocks() {
+        let data: Vec<u8> = (0..150_000).map(|i| (i % 251) as u8).collect();
+        let stream = zlib_stored(&data);
+
+        assert_eq!(u16::from_be_bytes([stream[0], stream[1]]) % 31, 0);
+        assert_eq!(stream.len(), data.len() + 2 + 3 * 5 + 4);
+        assert_eq!(inflate_stored(&stream), data);
+        assert_eq!(inflate_stored(&zlib_stored(&[])), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn writes_png_chunks() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.clear([10, 20, 30, 255]);
+        let mut png = Vec::new();
+        write_png(&canvas, &mut png).unwrap();
+
+        assert_eq!(&png[..8], &SIGNATURE);
+
+        let mut chunks = Vec::new();
+        let mut at = 8;
+        while at < png.len() {
+            let length = u32::from_be_bytes([png[at], png[at + 1], png[at + 2], png[at + 3]]);
+            let end = at + 8 + length as usize;
+            let mut crc = Crc32::new();
+            crc.update(&png[at + 4..end]);
+            assert_eq!(&png[end..end + 4], &crc.finish().to_be_bytes());
+
+            chunks.push((&png[at + 4..at + 8], &png[at + 8..end]));
+            at = end + 4;
+        }
+
+        let kinds: Vec<&[u8]> = chunks.iter().map(|chunk| chunk.0).collect();
+        assert_eq!(kinds, vec![&b"IHDR"[..], b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, &[0, 0, 0, 3, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+
+        let scanline = [0, 10, 20, 30, 255, 10, 20, 30, 255, 10, 20, 30, 255];
+        assert_eq!(inflate_stored(chunks[1].1), [scanline, scanline].concat());
+    }
+}
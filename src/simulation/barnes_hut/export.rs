@@ -0,0 +1,159 @@
+use super::{quad::Quad, tree::BarnesHutTree};
+use crate::vec2::Vec2;
+use std::io::{self, Write};
+
+/// Draws the cells of `tree` with north up on a `size` pixels wide square,
+/// marking the center of mass of each internal node in red and each body
+/// in black.
+pub fn write_svg<W: Write>(tree: &BarnesHutTree, size: f64, writer: &mut W) -> io::Result<()> {
+    let root = tree.quad().clone();
+    let to_pixels = |pos: &Vec2| {
+        let half_length = root.length() / 2.0;
+        (
+            (pos.x() - root.center().x() + half_length) / root.length() * size,
+            (root.center().y() + half_length - pos.y()) / root.length() * size,
+        )
+    };
+
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+        size
+    )?;
+
+    let mut result = Ok(());
+    tree.visit(&mut |node: &BarnesHutTree, _depth: usize| {
+        if result.is_ok() {
+            result = write_node(node, &to_pixels, size / root.length(), &mut *writer);
+        }
+        result.is_ok()
+    });
+    result?;
+
+    writeln!(writer, "</svg>")
+}
+
+fn write_node<W: Write>(
+    node: &BarnesHutTree,
+    to_pixels: &dyn Fn(&Vec2) -> (f64, f64),
+    scale: f64,
+    writer: &mut W,
+) -> io::Result<()> {
+    let quad: &Quad = node.quad();
+    let half_length = quad.length() / 2.0;
+    let (x, y) = to_pixels(&(quad.center().clone() + Vec2::new(-half_length, half_length)));
+    let length = quad.length() * scale;
+    writeln!(
+        writer,
+        r#"<rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" fill="none" stroke="gray" stroke-width="0.5"/>"#,
+        x, y, length, length
+    )?;
+
+    if let Some(body) = node.body() {
+        let (x, y) = to_pixels(body.pos());
+        let (radius, color) = if node.is_external() {
+            (1.5, "black")
+        } else {
+            (2.5, "red")
+        };
+        writeln!(
+            writer,
+            r#"<circle cx="{:.3}" cy="{:.3}" r="{}" fill="{}"/>"#,
+            x, y, radius, color
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes the node hierarchy of `tree` as a Graphviz digraph, labelling
+/// nodes with their body count and mass and edges with the quadrant.
+pub fn write_dot<W: Write>(tree: &BarnesHutTree, writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "digraph tree {{")?;
+    writeln!(writer, "    node [shape=box];")?;
+    write_dot_node(tree, &mut 0, writer)?;
+    writeln!(writer, "}}")
+}
+
+// Writes the subtree under `node`, numbering nodes from `next_id`, and
+// returns the id given to `node`.
+fn write_dot_node<W: Write>(
+    node: &BarnesHutTree,
+    next_id: &mut usize,
+    writer: &mut W,
+) -> io::Result<usize> {
+    let id = *next_id;
+    *next_id += 1;
+
+    let mass = node.body().map_or(0.0, |body| body.mass());
+    writeln!(
+        writer,
+        "    n{} [label=\"{} bodies\\nmass {:e}\"];",
+        id,
+        node.body_count(),
+        mass
+    )?;
+
+    let children = [
+        ("nw", node.northwest()),
+        ("sw", node.southwest()),
+        ("se", node.southeast()),
+        ("ne", node.northeast()),
+    ];
+    for &(quadrant, child) in children.iter() {
+        if let Some(child) = child {
+            let child_id = write_dot_node(child, next_id, writer)?;
+            writeln!(
+                writer,
+                "    n{} -> n{} [label=\"{}\"];",
+                id, child_id, quadrant
+            )?;
+        }
+    }
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+
+    fn tree() -> BarnesHutTree {
+        let mut tree = BarnesHutTree::new(Quad::new(Vec2::zero(), 100.0));
+        tree.insert(&Body::new(Vec2::new(-30.0, 30.0), Vec2::zero(), 1.0));
+        tree.insert(&Body::new(Vec2::new(30.0, -30.0), Vec2::zero(), 2.0));
+        tree.insert(&Body::new(Vec2::new(40.0, -40.0), Vec2::zero(), 3.0));
+        tree
+    }
+
+    #[test]
+    fn writes_svg() {
+        let mut svg = Vec::new();
+        write_svg(&tree(), 200.0, &mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect ").count(), 6);
+        assert_eq!(svg.matches(r#"fill="black""#).count(), 3);
+        assert_eq!(svg.matches(r#"fill="red""#).count(), 3);
+
+        // The root spans the image and the northwest body sits up left.
+        assert!(svg.contains(r#"<rect x="0.000" y="0.000" width="200.000" height="200.000" "#));
+        assert!(svg.contains(r#"<circle cx="40.000" cy="40.000" r="1.5" fill="black"/>"#));
+    }
+
+    #[test]
+    fn writes_dot() {
+        let mut dot = Vec::new();
+        write_dot(&tree(), &mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+
+        assert!(dot.starts_with("digraph tree {\n"));
+        assert!(dot.contains("    n0 [label=\"3 bodies\\nmass 6e0\"];\n"));
+        assert!(dot.contains("    n0 -> n1 [label=\"nw\"];\n"));
+        assert!(dot.contains("    n0 -> n2 [label=\"se\"];\n"));
+        assert_eq!(dot.matches(" -> ").count(), 5);
+    }
+}
@@ -1,36 +1,467 @@
+pub mod export;
 pub mod quad;
+pub mod stats;
 pub mod tree;
 
-use crate::{body::Body, simulation::Simulation};
+use crate::{
+    body::Body,
+    force_law::{MultipoleExpansion, Newtonian},
+    periodic::PeriodicBox,
+    simulation::Simulation,
+};
 use quad::Quad;
-use tree::BarnesHutTree;
+use stats::TreeStats;
+use std::collections::HashMap;
+use tree::{BarnesHutTree, OPENING_ANGLE};
 
-pub struct BarnesHut {
+pub struct BarnesHut<L: MultipoleExpansion = Newtonian> {
     quad: Quad,
+    law: L,
+    periodic_box: Option<PeriodicBox>,
+    opening_angle: f64,
+    max_group_size: Option<usize>,
+    rebuild_interval: Option<usize>,
+    max_imbalance: f64,
+    tree: Option<BarnesHutTree>,
+    tree_len: usize,
+    steps_since_rebuild: usize,
+    stats: TreeStats,
+    potential_energy: f64,
 }
 
 impl BarnesHut {
-    pub fn new(quad: Quad) -> BarnesHut {
-        BarnesHut { quad }
+    pub fn new(quad: Quad, g: f64) -> BarnesHut {
+        BarnesHut::with_law(quad, Newtonian::new(g))
     }
 }
 
-impl Simulation for BarnesHut {
-    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
-        let mut tree = BarnesHutTree::new(self.quad.clone());
+impl<L: MultipoleExpansion> BarnesHut<L> {
+    pub fn with_law(quad: Quad, law: L) -> BarnesHut<L> {
+        BarnesHut {
+            quad,
+            law,
+            periodic_box: None,
+            opening_angle: OPENING_ANGLE,
+            max_group_size: None,
+            rebuild_interval: None,
+            max_imbalance: 0.0,
+            tree: None,
+            tree_len: 0,
+            steps_since_rebuild: 0,
+            stats: TreeStats::default(),
+            potential_energy: 0.0,
+        }
+    }
 
-        for body in bodies.iter() {
-            if self.quad.contains(body.pos()) {
-                tree.insert(body);
+    /// Wraps bodies into `periodic_box`, which replaces the root quad, and
+    /// lets every node act from its nearest image. Combine with an `Ewald`
+    /// law to include the remaining images.
+    pub fn with_periodic_box(mut self, periodic_box: PeriodicBox) -> BarnesHut<L> {
+        self.quad = periodic_box.quad();
+        self.periodic_box = Some(periodic_box);
+        self
+    }
+
+    /// Ratio of node size to distance below which a node acts as a whole,
+    /// `tree::OPENING_ANGLE` by default. Smaller angles are more accurate
+    /// and slower.
+    pub fn with_opening_angle(mut self, opening_angle: f64) -> BarnesHut<L> {
+        self.opening_angle = opening_angle;
+        self
+    }
+
+    pub fn opening_angle(&self) -> f64 {
+        self.opening_angle
+    }
+
+    /// Walks the tree once per node of at most `max_group_size` bodies and
+    /// shares the resulting interaction list between them. Bodies in a
+    /// periodic box are still walked one by one.
+    pub fn with_group_walk(mut self, max_group_size: usize) -> BarnesHut<L> {
+        self.max_group_size = Some(max_group_size);
+        self
+    }
+
+    /// Keeps the tree between steps, refitting its moments and relocating
+    /// only bodies that left their leaf. The tree is rebuilt from scratch
+    /// every `rebuild_interval` steps, or sooner once its `imbalance`
+    /// exceeds `max_imbalance`.
+    pub fn with_incremental_rebuild(
+        mut self,
+        rebuild_interval: usize,
+        max_imbalance: f64,
+    ) -> BarnesHut<L> {
+        self.rebuild_interval = Some(rebuild_interval);
+        self.max_imbalance = max_imbalance;
+        self
+    }
+
+    pub fn potential_energy(&self) -> f64 {
+        self.potential_energy
+    }
+
+    /// Tree built and walked in the last step.
+    pub fn stats(&self) -> &TreeStats {
+        &self.stats
+    }
+
+    fn tree_for(&mut self, bodies: &[&mut Body]) -> BarnesHutTree {
+        if let (Some(rebuild_interval), Some(mut tree)) = (self.rebuild_interval, self.tree.take())
+        {
+            // Indices in the tree only match the bodies they were built for.
+            if self.steps_since_rebuild + 1 < rebuild_interval && bodies.len() == self.tree_len {
+                for index in tree.refit(bodies) {
+                    if self.quad.contains(bodies[index].pos()) {
+                        tree.insert_indexed(index, bodies[index]);
+                    }
+                }
+
+                // Bodies entering the root quad from outside are not in the
+                // tree.
+                let inside = bodies
+                    .iter()
+                    .filter(|body| !body.is_test_particle() && self.quad.contains(body.pos()))
+                    .count();
+
+                if tree.body_count() == inside && tree.imbalance() <= self.max_imbalance {
+                    self.steps_since_rebuild += 1;
+                    return tree;
+                }
             }
         }
 
-        for body in bodies {
+        let mut tree = BarnesHutTree::new(self.quad.clone());
+        for (index, body) in bodies.iter().enumerate() {
+            if !body.is_test_particle() && self.quad.contains(body.pos()) {
+                tree.insert_indexed(index, body);
+            }
+        }
+        self.tree_len = bodies.len();
+        self.steps_since_rebuild = 0;
+
+        tree
+    }
+}
+
+impl<L: MultipoleExpansion> Simulation for BarnesHut<L> {
+    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        if let Some(periodic_box) = &self.periodic_box {
+            periodic_box.wrap_bodies(bodies);
+        }
+
+        let tree = self.tree_for(bodies);
+
+        let mut potential_energy = 0.0;
+        let mut interaction_lists = HashMap::new();
+        let mut interactions = vec![0; bodies.len()];
+
+        for (body, interactions) in bodies.iter_mut().zip(interactions.iter_mut()) {
             body.reset_force();
             if self.quad.contains(body.pos()) {
-                tree.update_force(body);
+                let group = self
+                    .max_group_size
+                    .and_then(|max_group_size| tree.group_of(body.pos(), max_group_size));
+
+                *interactions = match (&self.periodic_box, group) {
+                    (None, Some(group)) => {
+                        // No two nodes of a tree share a quad.
+                        let quad = group.quad();
+                        let key = (
+                            quad.center().x().to_bits(),
+                            quad.center().y().to_bits(),
+                            quad.length().to_bits(),
+                        );
+                        let list = interaction_lists
+                            .entry(key)
+                            .or_insert_with(|| tree.interaction_list(quad, self.opening_angle));
+
+                        let mut count = 0;
+                        for other in list.iter() {
+                            // The group's own leaves include the body itself.
+                            if other.pos() != body.pos() {
+                                body.add_force(other, &self.law);
+                                count += 1;
+                            }
+                        }
+                        count
+                    }
+                    (periodic_box, _) => tree.update_force_with_opening_angle(
+                        body,
+                        &self.law,
+                        self.opening_angle,
+                        periodic_box.as_ref(),
+                    ),
+                };
+                if !body.is_test_particle() {
+                    potential_energy += body.potential();
+                }
                 body.update(dt);
             }
         }
+
+        // Every pair of massive bodies is counted once from each side.
+        self.potential_energy = potential_energy / 2.0;
+        self.stats = TreeStats::new(&tree, interactions);
+
+        if self.rebuild_interval.is_some() {
+            self.tree = Some(tree);
+        }
+
+        if let Some(periodic_box) = &self.periodic_box {
+            periodic_box.wrap_bodies(bodies);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{accuracy::ForceErrors, simulation::brute_force::BruteForce, units::G, vec2::Vec2};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn calculates_potential_energy_of_direct_summation() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut bodies: Vec<Body> = (0..200)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-50.0, 50.0), rng.gen_range(-50.0, 50.0)),
+                    Vec2::zero(),
+                    rng.gen_range(1.0, 10.0),
+                )
+            })
+            .collect();
+
+        let mut direct_bodies = bodies.clone();
+        BruteForce::new(G).step(&mut direct_bodies.iter_mut().collect(), 0.0);
+        let direct_potential_energy =
+            direct_bodies.iter().map(|b| b.potential()).sum::<f64>() / 2.0;
+
+        let mut simulation =
+            BarnesHut::new(Quad::new(Vec2::zero(), 100.0), G).with_opening_angle(0.0);
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        let relative_error = ((simulation.potential_energy() - direct_potential_energy)
+            / direct_potential_energy)
+            .abs();
+        assert!(relative_error < 1e-12, "relative error: {}", relative_error);
+
+        assert_eq!(
+            bodies.iter().map(|b| b.potential()).sum::<f64>() / 2.0,
+            simulation.potential_energy()
+        );
+    }
+
+    #[test]
+    fn approximates_given_force_law() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let bodies: Vec<Body> = (0..300)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-50.0, 50.0), rng.gen_range(-50.0, 50.0)),
+                    Vec2::zero(),
+                    rng.gen_range(1.0, 10.0),
+                )
+            })
+            .collect();
+
+        let errors = ForceErrors::against(
+            &bodies,
+            &mut BruteForce::with_law(Newtonian::new(2.0)),
+            &mut BarnesHut::with_law(Quad::new(Vec2::zero(), 100.0), Newtonian::new(2.0))
+                .with_opening_angle(0.5),
+        );
+
+        assert_eq!(errors.len(), bodies.len());
+        assert!(errors.max() > 0.0);
+        assert!(errors.median() < 1e-2);
+        assert!(errors.percentile(99.0) < 1e-1);
+    }
+
+    #[test]
+    fn leaves_test_particles_out_of_tree() {
+        let massive = vec![
+            Body::new(Vec2::new(-10.0, 5.0), Vec2::zero(), 3.0),
+            Body::new(Vec2::new(20.0, -5.0), Vec2::zero(), 1.0),
+            Body::new(Vec2::new(25.0, 30.0), Vec2::zero(), 2.0),
+        ];
+        let mut bodies = massive.clone();
+        bodies.push(Body::new(Vec2::new(-30.0, -40.0), Vec2::zero(), 5.0).with_test_particle(true));
+        bodies.push(Body::new(Vec2::new(0.0, 10.0), Vec2::zero(), 5.0).with_test_particle(true));
+
+        let mut simulation = BarnesHut::new(Quad::new(Vec2::zero(), 100.0), 1.0);
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        let mut massive_only = massive;
+        let mut massive_simulation = BarnesHut::new(Quad::new(Vec2::zero(), 100.0), 1.0);
+        massive_simulation.step(&mut massive_only.iter_mut().collect(), 0.0);
+
+        assert_eq!(&bodies[..3], &massive_only[..]);
+        assert_eq!(
+            simulation.potential_energy(),
+            massive_simulation.potential_energy()
+        );
+
+        let mut tree = BarnesHutTree::new(Quad::new(Vec2::zero(), 100.0));
+        for body in &massive_only {
+            tree.insert(body);
+        }
+        for tracer in &bodies[3..] {
+            let mut expected = tracer.clone();
+            expected.reset_force();
+            tree.update_force(&mut expected, &Newtonian::new(1.0));
+
+            assert_eq!(tracer.force(), expected.force());
+        }
+    }
+
+    #[test]
+    fn group_walk_is_as_accurate_as_walking_each_body() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let bodies: Vec<Body> = (0..300)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-50.0, 50.0), rng.gen_range(-50.0, 50.0)),
+                    Vec2::zero(),
+                    rng.gen_range(1.0, 10.0),
+                )
+            })
+            .collect();
+
+        let mut direct_bodies = bodies.clone();
+        BruteForce::new(1.0).step(&mut direct_bodies.iter_mut().collect(), 0.0);
+
+        let quad = Quad::new(Vec2::zero(), 100.0);
+        let rms_error = |simulation: &mut BarnesHut| {
+            let mut tree_bodies = bodies.clone();
+            simulation.step(&mut tree_bodies.iter_mut().collect(), 0.0);
+
+            let squared_error: f64 = tree_bodies
+                .iter()
+                .zip(&direct_bodies)
+                .map(|(body, direct)| {
+                    (body.force().dist(direct.force()) / direct.force().length()).powi(2)
+                })
+                .sum();
+            (squared_error / bodies.len() as f64).sqrt()
+        };
+
+        let walk_error = rms_error(&mut BarnesHut::new(quad.clone(), 1.0));
+        for &max_group_size in [1, 8, 32].iter() {
+            let group_error =
+                rms_error(&mut BarnesHut::new(quad.clone(), 1.0).with_group_walk(max_group_size));
+
+            assert!(
+                group_error <= walk_error,
+                "group of {}: {} against {}",
+                max_group_size,
+                group_error,
+                walk_error
+            );
+        }
+
+        let mut whole_tree = BarnesHut::new(quad, 1.0).with_group_walk(bodies.len());
+        assert!(rms_error(&mut whole_tree) < 1e-12);
+    }
+
+    #[test]
+    fn incremental_rebuild_follows_full_rebuild() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let bodies: Vec<Body> = (0..200)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-40.0, 40.0), rng.gen_range(-40.0, 40.0)),
+                    Vec2::new(rng.gen_range(-5.0, 5.0), rng.gen_range(-5.0, 5.0)),
+                    rng.gen_range(1.0, 10.0),
+                )
+            })
+            .collect();
+
+        let quad = Quad::new(Vec2::zero(), 100.0);
+        let mut full_bodies = bodies.clone();
+        let mut full = BarnesHut::new(quad.clone(), 1.0);
+        let mut incremental_bodies = bodies;
+        let mut incremental = BarnesHut::new(quad, 1.0).with_incremental_rebuild(8, 0.5);
+
+        for _ in 0..20 {
+            full.step(&mut full_bodies.iter_mut().collect(), 0.1);
+            incremental.step(&mut incremental_bodies.iter_mut().collect(), 0.1);
+        }
+
+        for (body, expected) in incremental_bodies.iter().zip(&full_bodies) {
+            assert!(body.pos().dist(expected.pos()) < 1e-9);
+        }
+        let relative_error = ((incremental.potential_energy() - full.potential_energy())
+            / full.potential_energy())
+        .abs();
+        assert!(relative_error < 1e-12);
+    }
+
+    #[test]
+    fn rebuilds_tree_after_bodies_are_removed() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut bodies: Vec<Body> = (0..20)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-40.0, 40.0), rng.gen_range(-40.0, 40.0)),
+                    Vec2::zero(),
+                    rng.gen_range(1.0, 10.0),
+                )
+            })
+            .collect();
+
+        let quad = Quad::new(Vec2::zero(), 100.0);
+        let mut incremental = BarnesHut::new(quad.clone(), 1.0).with_incremental_rebuild(8, 0.5);
+        incremental.step(&mut bodies.iter_mut().collect(), 0.1);
+
+        bodies.truncate(10);
+        let mut expected = bodies.clone();
+        incremental.step(&mut bodies.iter_mut().collect(), 0.1);
+        BarnesHut::new(quad, 1.0).step(&mut expected.iter_mut().collect(), 0.1);
+
+        assert_eq!(incremental.stats().body_count(), 10);
+        for (body, expected) in bodies.iter().zip(&expected) {
+            assert!(body.pos().dist(expected.pos()) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn reports_tree_stats_after_step() {
+        let mut bodies: Vec<Body> = (0..50)
+            .map(|i| {
+                Body::new(
+                    Vec2::new((i % 10) as f64, (i / 10) as f64),
+                    Vec2::zero(),
+                    1.0,
+                )
+            })
+            .collect();
+        bodies.push(Body::new(Vec2::new(0.5, 0.5), Vec2::zero(), 1.0).with_test_particle(true));
+        let quad = Quad::new(Vec2::new(5.0, 5.0), 20.0);
+
+        for simulation in &mut [
+            BarnesHut::new(quad.clone(), 1.0).with_opening_angle(0.0),
+            BarnesHut::new(quad.clone(), 1.0)
+                .with_opening_angle(0.0)
+                .with_group_walk(8),
+        ] {
+            simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+            let stats = simulation.stats();
+            assert_eq!(stats.body_count(), 50);
+            assert_eq!(stats.leaf_count(), 50);
+            assert_eq!(stats.depth_histogram()[0], 1);
+            assert_eq!(
+                stats.depth_histogram().iter().sum::<usize>(),
+                stats.node_count()
+            );
+            // Without approximations every body feels every other one.
+            assert!(stats.interactions()[..50].iter().all(|&count| count == 49));
+            assert_eq!(stats.interactions()[50], 50);
+        }
+
+        let mut loose = BarnesHut::new(quad, 1.0);
+        loose.step(&mut bodies.iter_mut().collect(), 0.0);
+        assert!(loose.stats().mean_interactions() < 49.0);
     }
 }
@@ -20,6 +20,25 @@ impl Quad {
             && point.y() >= self.center.y() - half_length
     }
 
+    pub fn intersects(&self, other: &Quad) -> bool {
+        let reach = (self.length + other.length) / 2.0;
+
+        (self.center.x() - other.center.x()).abs() <= reach
+            && (self.center.y() - other.center.y()).abs() <= reach
+    }
+
+    /// Distance from `point` to the closest point of the quad, zero inside.
+    pub fn distance_to(&self, point: &Vec2) -> f64 {
+        let half_length = self.length / 2.0;
+        let outside = |x: f64, center: f64| ((x - center).abs() - half_length).max(0.0);
+
+        outside(point.x(), self.center.x()).hypot(outside(point.y(), self.center.y()))
+    }
+
+    pub fn center(&self) -> &Vec2 {
+        &self.center
+    }
+
     pub fn length(&self) -> f64 {
         self.length
     }
@@ -84,6 +103,27 @@ mod tests {
         assert!(!node.contains(&Vec2::new(-25.0, -15.0)));
     }
 
+    #[test]
+    fn returns_whether_intersects_another_quad() {
+        let node = Quad::new(Vec2::zero(), 40.0);
+
+        assert!(node.intersects(&Quad::new(Vec2::new(5.0, 5.0), 2.0)));
+        assert!(node.intersects(&Quad::new(Vec2::new(25.0, -25.0), 10.0)));
+        assert!(node.intersects(&Quad::new(Vec2::zero(), 100.0)));
+
+        assert!(!node.intersects(&Quad::new(Vec2::new(25.0, 0.0), 8.0)));
+        assert!(!node.intersects(&Quad::new(Vec2::new(-10.0, -30.0), 10.0)));
+    }
+
+    #[test]
+    fn returns_distance_to_a_point() {
+        let node = Quad::new(Vec2::zero(), 40.0);
+
+        assert_eq!(node.distance_to(&Vec2::new(5.0, -20.0)), 0.0);
+        assert_eq!(node.distance_to(&Vec2::new(30.0, 10.0)), 10.0);
+        assert_eq!(node.distance_to(&Vec2::new(-23.0, -24.0)), 5.0);
+    }
+
     #[test]
     fn returns_subdivisions() {
         let node = Quad::new(Vec2::zero(), 40.0);
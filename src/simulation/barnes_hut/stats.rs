@@ -0,0 +1,113 @@
+use super::tree::BarnesHutTree;
+use std::mem;
+
+/// Shape of a Barnes-Hut tree and the interactions walking it took.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct TreeStats {
+    node_count: usize,
+    leaf_count: usize,
+    body_count: usize,
+    depth_histogram: Vec<usize>,
+    interactions: Vec<usize>,
+}
+
+impl TreeStats {
+    /// `interactions` holds the number of nodes that acted on each body.
+    pub fn new(tree: &BarnesHutTree, interactions: Vec<usize>) -> TreeStats {
+        let mut stats = TreeStats {
+            body_count: tree.body_count(),
+            interactions,
+            ..TreeStats::default()
+        };
+
+        tree.visit(&mut |node: &BarnesHutTree, depth: usize| {
+            stats.node_count += 1;
+            if node.is_external() && node.body().is_some() {
+                stats.leaf_count += 1;
+            }
+            if stats.depth_histogram.len() <= depth {
+                stats.depth_histogram.resize(depth + 1, 0);
+            }
+            stats.depth_histogram[depth] += 1;
+            true
+        });
+
+        stats
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.body_count
+    }
+
+    /// Depth of the deepest node, the root being at depth 0.
+    pub fn depth(&self) -> usize {
+        self.depth_histogram.len().saturating_sub(1)
+    }
+
+    /// Number of nodes at each depth.
+    pub fn depth_histogram(&self) -> &[usize] {
+        &self.depth_histogram
+    }
+
+    pub fn bodies_per_leaf(&self) -> f64 {
+        if self.leaf_count == 0 {
+            0.0
+        } else {
+            self.body_count as f64 / self.leaf_count as f64
+        }
+    }
+
+    /// Bytes taken by the nodes of the tree.
+    pub fn memory_footprint(&self) -> usize {
+        self.node_count * mem::size_of::<BarnesHutTree>()
+    }
+
+    /// Number of nodes that acted on each body, in the order of the bodies.
+    pub fn interactions(&self) -> &[usize] {
+        &self.interactions
+    }
+
+    pub fn mean_interactions(&self) -> f64 {
+        if self.interactions.is_empty() {
+            0.0
+        } else {
+            self.interactions.iter().sum::<usize>() as f64 / self.interactions.len() as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Body, simulation::barnes_hut::quad::Quad, vec2::Vec2};
+
+    #[test]
+    fn describes_tree_shape() {
+        let mut tree = BarnesHutTree::new(Quad::new(Vec2::zero(), 100.0));
+        tree.insert(&Body::new(Vec2::new(-30.0, 30.0), Vec2::zero(), 1.0));
+        tree.insert(&Body::new(Vec2::new(30.0, -30.0), Vec2::zero(), 1.0));
+        tree.insert(&Body::new(Vec2::new(40.0, -40.0), Vec2::zero(), 1.0));
+
+        let stats = TreeStats::new(&tree, vec![2, 2, 1]);
+
+        // Root, two quadrants, the southeast one split twice more.
+        assert_eq!(stats.depth_histogram(), &[1, 2, 1, 2]);
+        assert_eq!(stats.depth(), 3);
+        assert_eq!(stats.node_count(), 6);
+        assert_eq!(stats.leaf_count(), 3);
+        assert_eq!(stats.bodies_per_leaf(), 1.0);
+        assert_eq!(
+            stats.memory_footprint(),
+            6 * mem::size_of::<BarnesHutTree>()
+        );
+        assert!((stats.mean_interactions() - 5.0 / 3.0).abs() < 1e-15);
+    }
+}
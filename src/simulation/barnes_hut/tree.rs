@@ -1,9 +1,31 @@
-use crate::{body::Body, simulation::barnes_hut::quad::Quad};
+use crate::{
+    body::Body, force_law::MultipoleExpansion, periodic::PeriodicBox,
+    simulation::barnes_hut::quad::Quad, vec2::Vec2,
+};
+use std::borrow::Cow;
+
+/// Ratio of node size to distance below which `update_force` uses a node
+/// as a whole.
+pub const OPENING_ANGLE: f64 = 2.0;
+
+/// Callback for `BarnesHutTree::visit`, implemented by closures taking the
+/// node and its depth.
+pub trait Visitor {
+    fn visit(&mut self, node: &BarnesHutTree, depth: usize) -> bool;
+}
+
+impl<F: FnMut(&BarnesHutTree, usize) -> bool> Visitor for F {
+    fn visit(&mut self, node: &BarnesHutTree, depth: usize) -> bool {
+        self(node, depth)
+    }
+}
 
 #[derive(Debug)]
 pub struct BarnesHutTree {
     quad: Quad,
     body: Option<Body>,
+    body_count: usize,
+    index: Option<usize>,
     northwest: Option<Box<BarnesHutTree>>,
     northeast: Option<Box<BarnesHutTree>>,
     southeast: Option<Box<BarnesHutTree>>,
@@ -15,6 +37,8 @@ impl BarnesHutTree {
         BarnesHutTree {
             quad,
             body: None,
+            body_count: 0,
+            index: None,
             northwest: None,
             northeast: None,
             southeast: None,
@@ -23,52 +47,347 @@ impl BarnesHutTree {
     }
 
     pub fn insert(&mut self, body: &Body) {
+        self.insert_entry(body, None);
+    }
+
+    /// Inserts `body` as entry `index` of the body list later given to
+    /// `refit`.
+    pub fn insert_indexed(&mut self, index: usize, body: &Body) {
+        self.insert_entry(body, Some(index));
+    }
+
+    fn insert_entry(&mut self, body: &Body, index: Option<usize>) {
         if self.body.is_none() {
             self.body = Some(body.clone());
+            self.body_count = 1;
+            self.index = index;
             return;
         }
 
         if !self.is_external() {
             self.body = Some(self.body.as_ref().unwrap().add(body));
-            self.insert_proper_quad(&body);
+            self.insert_proper_quad(body, index);
+            self.body_count += 1;
             return;
         }
 
-        self.insert_proper_quad(&self.body.clone().unwrap());
-        self.insert(&body);
+        let existing_index = self.index.take();
+        self.insert_proper_quad(&self.body.clone().unwrap(), existing_index);
+        self.insert_entry(body, index);
+    }
+
+    /// Moves indexed leaves to the current state of `bodies` and recomputes
+    /// node moments bottom-up. Bodies that left their leaf are removed and
+    /// their indices returned for reinsertion.
+    pub fn refit(&mut self, bodies: &[&mut Body]) -> Vec<usize> {
+        let mut escaped = Vec::new();
+        self.refit_into(bodies, &mut escaped);
+        escaped
     }
 
-    pub fn update_force(&self, body: &mut Body) {
+    fn refit_into(&mut self, bodies: &[&mut Body], escaped: &mut Vec<usize>) {
         if self.is_external() {
-            if let Some(current_body) = &self.body {
-                body.add_force(&current_body);
+            if let Some(index) = self.index {
+                // A body no longer in the list counts as escaped.
+                match bodies.get(index) {
+                    Some(body) if self.quad.contains(body.pos()) => {
+                        self.body = Some((*body).clone());
+                    }
+                    _ => {
+                        escaped.push(index);
+                        self.body = None;
+                        self.body_count = 0;
+                        self.index = None;
+                    }
+                }
             }
-
             return;
         }
 
-        if self.body.is_none() {
-            return;
+        let mut total: Option<Body> = None;
+        let mut body_count = 0;
+        let children = [
+            &mut self.northwest,
+            &mut self.northeast,
+            &mut self.southwest,
+            &mut self.southeast,
+        ];
+        for child in children {
+            let emptied = match child.as_mut() {
+                Some(node) => {
+                    node.refit_into(bodies, escaped);
+                    match &node.body {
+                        Some(body) => {
+                            total = Some(match total {
+                                Some(total) => total.add(body),
+                                None => body.clone(),
+                            });
+                            body_count += node.body_count;
+                            false
+                        }
+                        None => true,
+                    }
+                }
+                None => false,
+            };
+            if emptied {
+                *child = None;
+            }
+        }
+
+        self.body = total;
+        self.body_count = body_count;
+    }
+
+    /// Fraction of internal nodes holding a single body. A fresh tree has
+    /// none; `refit` leaves them behind as bodies move out of a node.
+    pub fn imbalance(&self) -> f64 {
+        let (stale, internal) = self.count_stale_nodes();
+        if internal == 0 {
+            0.0
+        } else {
+            stale as f64 / internal as f64
         }
+    }
 
-        let current_body = self.body.as_ref().unwrap();
-        if (self.quad.length() / current_body.pos().dist(body.pos())) < 2.0 {
-            body.add_force(current_body);
+    fn count_stale_nodes(&self) -> (usize, usize) {
+        if self.is_external() {
+            return (0, 0);
+        }
+
+        let children = [
+            &self.northwest,
+            &self.northeast,
+            &self.southwest,
+            &self.southeast,
+        ];
+        let (stale, internal) = children
+            .iter()
+            .copied()
+            .flatten()
+            .map(|child| child.count_stale_nodes())
+            .fold((0, 0), |(a, b), (c, d)| (a + c, b + d));
+
+        (stale + (self.body_count == 1) as usize, internal + 1)
+    }
+
+    pub fn update_force<L: MultipoleExpansion>(&self, body: &mut Body, law: &L) {
+        self.walk(body, law, None, OPENING_ANGLE, f64::INFINITY);
+    }
+
+    /// Like `update_force`, but each node acts from its image nearest to the
+    /// body.
+    pub fn update_periodic_force<L: MultipoleExpansion>(
+        &self,
+        body: &mut Body,
+        law: &L,
+        periodic_box: &PeriodicBox,
+    ) {
+        self.walk(body, law, Some(periodic_box), OPENING_ANGLE, f64::INFINITY);
+    }
+
+    /// `update_force`, or `update_periodic_force` given a box, with another
+    /// opening angle. Returns the number of nodes that acted on the body.
+    pub fn update_force_with_opening_angle<L: MultipoleExpansion>(
+        &self,
+        body: &mut Body,
+        law: &L,
+        opening_angle: f64,
+        periodic_box: Option<&PeriodicBox>,
+    ) -> usize {
+        self.walk(body, law, periodic_box, opening_angle, f64::INFINITY)
+    }
+
+    /// Calls `visitor` on this node and its descendants depth first, the
+    /// root at depth 0. Children of a node are skipped when the visitor
+    /// returns false for it.
+    pub fn visit<V: Visitor>(&self, visitor: &mut V) {
+        self.visit_at(visitor, 0);
+    }
+
+    fn visit_at<V: Visitor>(&self, visitor: &mut V, depth: usize) {
+        if !visitor.visit(self, depth) {
             return;
         }
 
+        let children = [
+            &self.northwest,
+            &self.southwest,
+            &self.southeast,
+            &self.northeast,
+        ];
+        for child in children.iter().copied().flatten() {
+            child.visit_at(visitor, depth + 1);
+        }
+    }
+
+    /// Like `update_force` with the given opening angle, but skips nodes
+    /// lying entirely farther than `cutoff` from the body, for laws that
+    /// vanish beyond it.
+    pub fn update_short_range_force<L: MultipoleExpansion>(
+        &self,
+        body: &mut Body,
+        law: &L,
+        opening_angle: f64,
+        cutoff: f64,
+        periodic_box: Option<&PeriodicBox>,
+    ) {
+        self.walk(body, law, periodic_box, opening_angle, cutoff);
+    }
+
+    // Returns the number of nodes that acted on the body.
+    fn walk<L: MultipoleExpansion>(
+        &self,
+        body: &mut Body,
+        law: &L,
+        periodic_box: Option<&PeriodicBox>,
+        opening_angle: f64,
+        cutoff: f64,
+    ) -> usize {
+        let current_body = match &self.body {
+            Some(current_body) => current_body,
+            None => return 0,
+        };
+        if cutoff < f64::INFINITY && self.distance_to(body, periodic_box) > cutoff {
+            return 0;
+        }
+
+        let current_body = match periodic_box {
+            Some(periodic_box) => Cow::Owned(periodic_box.nearest_body(body, current_body)),
+            None => Cow::Borrowed(current_body),
+        };
+
+        if self.is_external() {
+            // The leaf holding the body itself exerts no force on it.
+            if current_body.pos() == body.pos() {
+                return 0;
+            }
+
+            body.add_force(&current_body, law);
+            return 1;
+        }
+
+        if self.fits_nearest_image(body, periodic_box)
+            && (self.quad.length() / current_body.pos().dist(body.pos())) < opening_angle
+        {
+            body.add_force(&current_body, law);
+            return 1;
+        }
+
+        let mut interactions = 0;
         if let Some(northwest) = &self.northwest {
-            northwest.update_force(body);
+            interactions += northwest.walk(body, law, periodic_box, opening_angle, cutoff);
         }
         if let Some(southwest) = &self.southwest {
-            southwest.update_force(body);
+            interactions += southwest.walk(body, law, periodic_box, opening_angle, cutoff);
         }
         if let Some(southeast) = &self.southeast {
-            southeast.update_force(body);
+            interactions += southeast.walk(body, law, periodic_box, opening_angle, cutoff);
         }
         if let Some(northeast) = &self.northeast {
-            northeast.update_force(body);
+            interactions += northeast.walk(body, law, periodic_box, opening_angle, cutoff);
+        }
+
+        interactions
+    }
+
+    /// Largest node holding `pos` with at most `max_group_size` bodies,
+    /// if `pos` falls into one.
+    pub fn group_of(&self, pos: &Vec2, max_group_size: usize) -> Option<&BarnesHutTree> {
+        if !self.quad.contains(pos) {
+            return None;
+        }
+
+        if self.body_count <= max_group_size {
+            return Some(self);
         }
+
+        // Same precedence as `insert_proper_quad` for points on a border.
+        let child = if self.quad.northwest().contains(pos) {
+            &self.northwest
+        } else if self.quad.northeast().contains(pos) {
+            &self.northeast
+        } else if self.quad.southwest().contains(pos) {
+            &self.southwest
+        } else {
+            &self.southeast
+        };
+
+        child.as_ref()?.group_of(pos, max_group_size)
+    }
+
+    /// Nodes acting on every body inside `group`. A node is used as a whole
+    /// only if it passes the opening criterion from the closest point of
+    /// `group`; leaves may include the bodies of the group themselves.
+    pub fn interaction_list(&self, group: &Quad, opening_angle: f64) -> Vec<Body> {
+        let mut list = Vec::new();
+        self.collect_interactions(group, opening_angle, &mut list);
+        list
+    }
+
+    fn collect_interactions(&self, group: &Quad, opening_angle: f64, list: &mut Vec<Body>) {
+        let current_body = match &self.body {
+            Some(body) => body,
+            None => return,
+        };
+
+        if self.is_external() {
+            list.push(current_body.clone());
+            return;
+        }
+
+        if !self.quad.intersects(group)
+            && self.quad.length() / group.distance_to(current_body.pos()) < opening_angle
+        {
+            list.push(current_body.clone());
+            return;
+        }
+
+        let children = [
+            &self.northwest,
+            &self.southwest,
+            &self.southeast,
+            &self.northeast,
+        ];
+        for child in children.iter().copied().flatten() {
+            child.collect_interactions(group, opening_angle, list);
+        }
+    }
+
+    // Distance from the body to the closest point of the node.
+    fn distance_to(&self, body: &Body, periodic_box: Option<&PeriodicBox>) -> f64 {
+        let mut separation = self.quad.center().clone() - body.pos().clone();
+        if let Some(periodic_box) = periodic_box {
+            separation = periodic_box.nearest_image(&separation);
+        }
+
+        let half_length = self.quad.length() / 2.0;
+        (separation.x().abs() - half_length)
+            .max(0.0)
+            .hypot((separation.y().abs() - half_length).max(0.0))
+    }
+
+    // Whether the whole node lies within half a box of the body, so that
+    // its bodies all share the image nearest to it.
+    fn fits_nearest_image(&self, body: &Body, periodic_box: Option<&PeriodicBox>) -> bool {
+        let periodic_box = match periodic_box {
+            Some(periodic_box) => periodic_box,
+            None => return true,
+        };
+
+        let reach = (periodic_box.size() - self.quad.length()) / 2.0;
+        let separation =
+            periodic_box.nearest_image(&(self.quad.center().clone() - body.pos().clone()));
+
+        separation.x().abs() <= reach && separation.y().abs() <= reach
+    }
+
+    pub fn quad(&self) -> &Quad {
+        &self.quad
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.body_count
     }
 
     pub fn is_external(&self) -> bool {
@@ -82,6 +401,11 @@ impl BarnesHutTree {
         self.body.as_ref()
     }
 
+    /// Entry the body of a leaf was given to `insert_indexed` with.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
     pub fn northwest(&self) -> Option<&BarnesHutTree> {
         self.northwest.as_ref().map(|t| t.as_ref())
     }
@@ -98,12 +422,12 @@ impl BarnesHutTree {
         self.southwest.as_ref().map(|t| t.as_ref())
     }
 
-    fn insert_proper_quad(&mut self, body: &Body) {
+    fn insert_proper_quad(&mut self, body: &Body, index: Option<usize>) {
         let quad_northwest = self.quad.northwest();
         if quad_northwest.contains(body.pos()) {
             self.northwest
                 .get_or_insert(Box::new(BarnesHutTree::new(quad_northwest)))
-                .insert(body);
+                .insert_entry(body, index);
             return;
         }
 
@@ -111,7 +435,7 @@ impl BarnesHutTree {
         if quad_northeast.contains(body.pos()) {
             self.northeast
                 .get_or_insert(Box::new(BarnesHutTree::new(quad_northeast)))
-                .insert(body);
+                .insert_entry(body, index);
             return;
         }
 
@@ -119,21 +443,21 @@ impl BarnesHutTree {
         if quad_southwest.contains(body.pos()) {
             self.southwest
                 .get_or_insert(Box::new(BarnesHutTree::new(quad_southwest)))
-                .insert(body);
+                .insert_entry(body, index);
             return;
         }
 
         let quad_southeast = self.quad.southeast();
         self.southeast
             .get_or_insert(Box::new(BarnesHutTree::new(quad_southeast)))
-            .insert(body);
+            .insert_entry(body, index);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::vec2::Vec2;
+    use crate::{force_law::Newtonian, units::G, vec2::Vec2};
 
     #[test]
     fn inserts_new_body_to_tree() {
@@ -145,32 +469,32 @@ mod tests {
         let fifth_body = Body::new(Vec2::new(-2.5, 2.5), Vec2::zero(), 20.0);
 
         tree.insert(&first_body);
-        assert_eq!(tree.is_external(), true);
+        assert!(tree.is_external());
         assert_eq!(tree.body(), Some(&first_body));
 
         tree.insert(&second_body);
-        assert_eq!(tree.is_external(), false);
+        assert!(!tree.is_external());
         assert_eq!(tree.body(), Some(&first_body.add(&second_body)));
-        assert_eq!(tree.northeast().unwrap().is_external(), true);
+        assert!(tree.northeast().unwrap().is_external());
         assert_eq!(tree.northeast().unwrap().body(), Some(&first_body));
-        assert_eq!(tree.southwest().unwrap().is_external(), true);
+        assert!(tree.southwest().unwrap().is_external());
         assert_eq!(tree.southwest().unwrap().body(), Some(&second_body));
 
         tree.insert(&third_body);
-        assert_eq!(tree.is_external(), false);
+        assert!(!tree.is_external());
         assert_eq!(
             tree.body(),
             Some(&first_body.add(&second_body).add(&third_body))
         );
-        assert_eq!(tree.northeast().unwrap().is_external(), true);
+        assert!(tree.northeast().unwrap().is_external());
         assert_eq!(tree.northeast().unwrap().body(), Some(&first_body));
-        assert_eq!(tree.southwest().unwrap().is_external(), true);
+        assert!(tree.southwest().unwrap().is_external());
         assert_eq!(tree.southwest().unwrap().body(), Some(&second_body));
-        assert_eq!(tree.southeast().unwrap().is_external(), true);
+        assert!(tree.southeast().unwrap().is_external());
         assert_eq!(tree.southeast().unwrap().body(), Some(&third_body));
 
         tree.insert(&fourth_body);
-        assert_eq!(tree.is_external(), false);
+        assert!(!tree.is_external());
         assert_eq!(
             tree.body(),
             Some(
@@ -180,17 +504,17 @@ mod tests {
                     .add(&fourth_body)
             )
         );
-        assert_eq!(tree.northeast().unwrap().is_external(), true);
+        assert!(tree.northeast().unwrap().is_external());
         assert_eq!(tree.northeast().unwrap().body(), Some(&first_body));
-        assert_eq!(tree.southwest().unwrap().is_external(), true);
+        assert!(tree.southwest().unwrap().is_external());
         assert_eq!(tree.southwest().unwrap().body(), Some(&second_body));
-        assert_eq!(tree.southeast().unwrap().is_external(), true);
+        assert!(tree.southeast().unwrap().is_external());
         assert_eq!(tree.southeast().unwrap().body(), Some(&third_body));
-        assert_eq!(tree.northwest().unwrap().is_external(), true);
+        assert!(tree.northwest().unwrap().is_external());
         assert_eq!(tree.northwest().unwrap().body(), Some(&fourth_body));
 
         tree.insert(&fifth_body);
-        assert_eq!(tree.is_external(), false);
+        assert!(!tree.is_external());
         assert_eq!(
             tree.body(),
             Some(
@@ -201,34 +525,28 @@ mod tests {
                     .add(&fifth_body)
             )
         );
-        assert_eq!(tree.northeast().unwrap().is_external(), true);
+        assert!(tree.northeast().unwrap().is_external());
         assert_eq!(tree.northeast().unwrap().body(), Some(&first_body));
-        assert_eq!(tree.southwest().unwrap().is_external(), true);
+        assert!(tree.southwest().unwrap().is_external());
         assert_eq!(tree.southwest().unwrap().body(), Some(&second_body));
-        assert_eq!(tree.southeast().unwrap().is_external(), true);
+        assert!(tree.southeast().unwrap().is_external());
         assert_eq!(tree.southeast().unwrap().body(), Some(&third_body));
-        assert_eq!(tree.northwest().unwrap().is_external(), false);
+        assert!(!tree.northwest().unwrap().is_external());
         assert_eq!(
             tree.northwest().unwrap().body(),
             Some(&fourth_body.add(&fifth_body))
         );
-        assert_eq!(tree.northwest().unwrap().is_external(), false);
+        assert!(!tree.northwest().unwrap().is_external());
         assert_eq!(
             tree.northwest().unwrap().body(),
             Some(&fourth_body.add(&fifth_body))
         );
-        assert_eq!(
-            tree.northwest().unwrap().southeast().unwrap().is_external(),
-            true
-        );
+        assert!(tree.northwest().unwrap().southeast().unwrap().is_external());
         assert_eq!(
             tree.northwest().unwrap().southeast().unwrap().body(),
             Some(&fourth_body)
         );
-        assert_eq!(
-            tree.northwest().unwrap().northwest().unwrap().is_external(),
-            true
-        );
+        assert!(tree.northwest().unwrap().northwest().unwrap().is_external());
         assert_eq!(
             tree.northwest().unwrap().northwest().unwrap().body(),
             Some(&fifth_body)
@@ -237,7 +555,7 @@ mod tests {
 
     #[test]
     fn updates_force_of_a_body() {
-        for (tree, expected_bodies_to_add_force) in vec![
+        for (tree, expected_bodies_to_add_force) in [
             (BarnesHutTree::new(Quad::new(Vec2::zero(), 10.0)), vec![]),
             (
                 {
@@ -277,14 +595,112 @@ mod tests {
         ] {
             let mut body = Body::new(Vec2::new(10.0, 5.0), Vec2::unit(), 5.0);
 
-            tree.update_force(&mut body);
+            tree.update_force(&mut body, &Newtonian::new(G));
 
             let mut twin_body = Body::new(Vec2::new(10.0, 5.0), Vec2::unit(), 5.0);
             for other_body in expected_bodies_to_add_force {
-                twin_body.add_force(other_body);
+                twin_body.add_force(other_body, &Newtonian::new(G));
             }
 
             assert_eq!(body.force(), twin_body.force());
+            assert_eq!(body.potential(), twin_body.potential());
+        }
+    }
+
+    #[test]
+    fn builds_interaction_list_for_a_group() {
+        let mut tree = BarnesHutTree::new(Quad::new(Vec2::zero(), 100.0));
+        let bodies = [
+            Body::new(Vec2::new(-40.0, 40.0), Vec2::zero(), 1.0),
+            Body::new(Vec2::new(-30.0, 45.0), Vec2::zero(), 2.0),
+            Body::new(Vec2::new(30.0, -30.0), Vec2::zero(), 3.0),
+            Body::new(Vec2::new(40.0, -40.0), Vec2::zero(), 4.0),
+            Body::new(Vec2::new(45.0, -45.0), Vec2::zero(), 5.0),
+        ];
+        for body in bodies.iter() {
+            tree.insert(body);
+        }
+        assert_eq!(tree.body_count(), 5);
+
+        let group = tree.group_of(bodies[0].pos(), 2).unwrap();
+        assert_eq!(group.quad(), &Quad::new(Vec2::new(-25.0, 25.0), 50.0));
+        assert_eq!(group.body_count(), 2);
+        assert!(tree.group_of(&Vec2::new(30.0, 30.0), 2).is_none());
+
+        // The far clump acts as a whole, the group's own leaves one by one.
+        let far_clump = bodies[2].add(&bodies[3]).add(&bodies[4]);
+        assert_eq!(
+            tree.interaction_list(group.quad(), 2.0),
+            vec![bodies[0].clone(), bodies[1].clone(), far_clump]
+        );
+
+        // A group covering the whole tree interacts with every body.
+        assert_eq!(tree.interaction_list(tree.quad(), 2.0).len(), 5);
+    }
+
+    #[test]
+    fn refits_tree_to_moved_bodies() {
+        let quad = Quad::new(Vec2::zero(), 100.0);
+        let mut bodies = [
+            Body::new(Vec2::new(-40.0, 40.0), Vec2::zero(), 1.0),
+            Body::new(Vec2::new(-30.0, 45.0), Vec2::zero(), 2.0),
+            Body::new(Vec2::new(30.0, -30.0), Vec2::zero(), 3.0),
+            Body::new(Vec2::new(40.0, -40.0), Vec2::zero(), 4.0),
+        ];
+
+        let mut tree = BarnesHutTree::new(quad.clone());
+        for (index, body) in bodies.iter().enumerate() {
+            tree.insert_indexed(index, body);
         }
+        assert_eq!(tree.imbalance(), 0.0);
+
+        // The first body stays in its leaf, the last one crosses the tree.
+        bodies[0].translate(Vec2::new(1.0, -1.0));
+        bodies[3].translate(Vec2::new(-80.0, 70.0));
+        let bodies: Vec<&mut Body> = bodies.iter_mut().collect();
+
+        let escaped = tree.refit(&bodies);
+        assert_eq!(escaped, vec![3]);
+        assert_eq!(tree.body_count(), 3);
+        for index in escaped {
+            tree.insert_indexed(index, bodies[index]);
+        }
+
+        let mut fresh = BarnesHutTree::new(quad);
+        for body in bodies.iter() {
+            fresh.insert(body);
+        }
+
+        let refitted = tree.body().unwrap();
+        let expected = fresh.body().unwrap();
+        assert_eq!(tree.body_count(), 4);
+        assert_eq!(refitted.mass(), expected.mass());
+        assert!(refitted.pos().dist(expected.pos()) < 1e-12);
+
+        // The southeast chain now leads to a single body.
+        assert!(tree.imbalance() > 0.0);
+    }
+
+    #[test]
+    fn visits_nodes_depth_first() {
+        let mut tree = BarnesHutTree::new(Quad::new(Vec2::zero(), 100.0));
+        tree.insert(&Body::new(Vec2::new(-30.0, 30.0), Vec2::zero(), 1.0));
+        tree.insert(&Body::new(Vec2::new(30.0, -30.0), Vec2::zero(), 1.0));
+        tree.insert(&Body::new(Vec2::new(40.0, -40.0), Vec2::zero(), 1.0));
+
+        let mut visited = Vec::new();
+        tree.visit(&mut |node: &BarnesHutTree, depth: usize| {
+            visited.push((node.quad().center().clone(), depth));
+            depth < 1
+        });
+
+        assert_eq!(
+            visited,
+            vec![
+                (Vec2::zero(), 0),
+                (Vec2::new(-25.0, 25.0), 1),
+                (Vec2::new(25.0, -25.0), 1),
+            ]
+        );
     }
 }
@@ -1,31 +1,44 @@
 use super::Simulation;
-use crate::body::Body;
+use crate::{
+    body::Body,
+    force_law::{ForceLaw, Newtonian},
+};
 use std::cell::RefCell;
 
-pub struct BruteForce;
+pub struct BruteForce<L: ForceLaw = Newtonian> {
+    law: L,
+}
 
 impl BruteForce {
-    pub fn new() -> BruteForce {
-        BruteForce
+    pub fn new(g: f64) -> BruteForce {
+        BruteForce::with_law(Newtonian::new(g))
+    }
+}
+
+impl<L: ForceLaw> BruteForce<L> {
+    pub fn with_law(law: L) -> BruteForce<L> {
+        BruteForce { law }
     }
 }
 
-impl Simulation for BruteForce {
+impl<L: ForceLaw> Simulation for BruteForce<L> {
     fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
         {
-            let bodies: Vec<RefCell<_>> = bodies.iter_mut().map(|b| RefCell::new(b)).collect();
+            let bodies: Vec<RefCell<_>> = bodies.iter_mut().map(RefCell::new).collect();
+            let sources: Vec<usize> = (0..bodies.len())
+                .filter(|&i| !bodies[i].borrow().is_test_particle())
+                .collect();
 
             for i in 0..bodies.len() {
                 let mut body = bodies[i].borrow_mut();
                 body.reset_force();
 
-                for j in 0..bodies.len() {
+                for &j in &sources {
                     if j == i {
                         continue;
                     }
 
-                    let other_body = bodies[j].borrow();
-                    body.add_force(&other_body);
+                    body.add_force(&bodies[j].borrow(), &self.law);
                 }
             }
         }
@@ -39,11 +52,11 @@ impl Simulation for BruteForce {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::vec2::Vec2;
+    use crate::{force_law::Coulomb, units::G, vec2::Vec2};
 
     #[test]
     fn calculates_next_state() {
-        let mut simulation = BruteForce::new();
+        let mut simulation = BruteForce::new(G);
 
         let mut body_1 = Body::new(Vec2::new(10.0, 9.0), Vec2::unit(), 10.0);
         let mut body_2 = Body::new(Vec2::new(7.0, 2.0), Vec2::unit(), 12.0);
@@ -56,28 +69,59 @@ mod tests {
         assert_eq!(
             bodies.iter().map(|b| b.force()).collect::<Vec<&Vec2>>(),
             vec![
-                &Vec2::new(2.2533832820136364e-10, 1.9529728777099549e-10),
-                &Vec2::new(2.7659106706034156e-11, -3.3205265799121046e-10),
-                &Vec2::new(-2.5299743490739776e-10, 1.3675537022021497e-10)
+                &Vec2::new(-2.2533832820136364e-10, -1.9529728777099549e-10),
+                &Vec2::new(-2.7659106706034156e-11, 3.3205265799121046e-10),
+                &Vec2::new(2.5299743490739776e-10, -1.3675537022021497e-10)
             ]
         );
 
         assert_eq!(
             bodies.iter().map(|b| b.velocity()).collect::<Vec<&Vec2>>(),
             vec![
-                &Vec2::new(3.2533832820136364, 2.952972877709955),
-                &Vec2::new(1.230492555883618, -1.767105483260087),
-                &Vec2::new(-1.162467936342472, 3.2094421277526872)
+                &Vec2::new(-1.2533832820136364, -0.9529728777099549),
+                &Vec2::new(0.7695074441163821, 3.767105483260087),
+                &Vec2::new(5.1624679363424715, -0.20944212775268722)
             ]
         );
 
         assert_eq!(
             bodies.iter().map(|b| b.pos()).collect::<Vec<&Vec2>>(),
             vec![
-                &Vec2::new(325338328211.36365, 295297287779.9955),
-                &Vec2::new(123049255595.3618, -176710548324.0087),
-                &Vec2::new(-116246793629.2472, 320944212782.26874)
+                &Vec2::new(-125338328191.36363, -95297287761.99548),
+                &Vec2::new(76950744418.6382, 376710548328.0087),
+                &Vec2::new(516246793639.24713, -20944212768.268723)
             ]
         );
     }
+
+    #[test]
+    fn uses_given_force_law() {
+        let mut simulation = BruteForce::with_law(Coulomb::new(2.0));
+
+        let mut body_1 = Body::new(Vec2::new(0.0, 0.0), Vec2::zero(), 1.0).with_charge(1.0);
+        let mut body_2 = Body::new(Vec2::new(2.0, 0.0), Vec2::zero(), 1.0).with_charge(3.0);
+
+        simulation.step(&mut vec![&mut body_1, &mut body_2], 1.0);
+
+        assert_eq!(body_1.force(), &Vec2::new(-1.5, 0.0));
+        assert_eq!(body_2.force(), &Vec2::new(1.5, 0.0));
+        assert_eq!(body_1.potential(), 3.0);
+    }
+
+    #[test]
+    fn moves_test_particles_without_their_pull() {
+        let mut simulation = BruteForce::new(1.0);
+
+        let mut star = Body::new(Vec2::new(0.0, 0.0), Vec2::zero(), 4.0);
+        let mut tracer = Body::new(Vec2::new(2.0, 0.0), Vec2::zero(), 3.0).with_test_particle(true);
+        let mut other_tracer =
+            Body::new(Vec2::new(2.0, 1.0), Vec2::zero(), 3.0).with_test_particle(true);
+
+        simulation.step(&mut vec![&mut star, &mut tracer, &mut other_tracer], 1.0);
+
+        assert_eq!(star.force(), &Vec2::zero());
+        assert_eq!(star.pos(), &Vec2::zero());
+        assert_eq!(tracer.force(), &Vec2::new(-3.0, 0.0));
+        assert_eq!(tracer.velocity(), &Vec2::new(-1.0, 0.0));
+    }
 }
@@ -0,0 +1,55 @@
+use super::Simulation;
+use crate::{body::Body, collision::bounce::Bounce};
+
+pub struct Collisional<S: Simulation> {
+    simulation: S,
+    bounce: Bounce,
+    contacts: usize,
+}
+
+impl<S: Simulation> Collisional<S> {
+    pub fn new(simulation: S, bounce: Bounce) -> Collisional<S> {
+        Collisional {
+            simulation,
+            bounce,
+            contacts: 0,
+        }
+    }
+
+    pub fn simulation(&self) -> &S {
+        &self.simulation
+    }
+
+    /// Number of overlapping pairs resolved in the last step.
+    pub fn contacts(&self) -> usize {
+        self.contacts
+    }
+}
+
+impl<S: Simulation> Simulation for Collisional<S> {
+    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        self.simulation.step(bodies, dt);
+        self.contacts = self.bounce.resolve(bodies);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{simulation::brute_force::BruteForce, units::G, vec2::Vec2};
+
+    #[test]
+    fn bounces_bodies_after_each_step() {
+        let mut simulation = Collisional::new(BruteForce::new(G), Bounce::elastic());
+
+        let mut first = Body::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), 1.0).with_radius(1.0);
+        let mut second = Body::new(Vec2::new(3.0, 0.0), Vec2::new(-1.0, 0.0), 1.0).with_radius(1.0);
+
+        simulation.step(&mut vec![&mut first, &mut second], 1.0);
+
+        assert_eq!(simulation.contacts(), 1);
+        assert!(first.velocity().x() < 0.0);
+        assert!(second.velocity().x() > 0.0);
+        assert!(first.pos().dist(second.pos()) >= 2.0 - 1e-12);
+    }
+}
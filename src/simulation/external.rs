@@ -0,0 +1,127 @@
+use super::Simulation;
+use crate::{body::Body, potential::ExternalPotential};
+
+/// Adds an external potential on top of another simulation.
+///
+/// The external acceleration is applied as half kicks around each inner
+/// step, so the combined integrator stays symplectic for a symplectic
+/// inner simulation.
+pub struct External<S: Simulation, P: ExternalPotential> {
+    simulation: S,
+    potential: P,
+    time: f64,
+}
+
+impl<S: Simulation, P: ExternalPotential> External<S, P> {
+    pub fn new(simulation: S, potential: P) -> External<S, P> {
+        External {
+            simulation,
+            potential,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_time(mut self, time: f64) -> External<S, P> {
+        self.time = time;
+        self
+    }
+
+    pub fn simulation(&self) -> &S {
+        &self.simulation
+    }
+
+    pub fn potential(&self) -> &P {
+        &self.potential
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Potential energy of the bodies in the external potential.
+    pub fn potential_energy(&self, bodies: &[Body]) -> f64 {
+        bodies
+            .iter()
+            .map(|b| b.mass() * self.potential.potential(b.pos(), self.time))
+            .sum()
+    }
+
+    fn kick(&self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        for body in bodies.iter_mut() {
+            let acceleration = self.potential.acceleration(body.pos(), self.time);
+            body.accelerate(acceleration, dt);
+        }
+    }
+}
+
+impl<S: Simulation, P: ExternalPotential> Simulation for External<S, P> {
+    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        self.kick(bodies, dt / 2.0);
+        self.simulation.step(bodies, dt);
+        self.time += dt;
+        self.kick(bodies, dt / 2.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ic::kinetic_energy,
+        potential::{Hernquist, Logarithmic, RotatingBar},
+        simulation::brute_force::BruteForce,
+        vec2::Vec2,
+    };
+
+    #[test]
+    fn keeps_tracer_on_circular_orbit() {
+        let potential = Logarithmic::new(1.0, 0.1);
+        let pos = Vec2::new(2.0, 0.0);
+        let velocity = Vec2::new(0.0, potential.circular_velocity(&pos, 0.0));
+
+        let mut simulation = External::new(BruteForce::new(1.0), potential);
+        let mut tracer = Body::new(pos, velocity, 1.0);
+
+        for _ in 0..10_000 {
+            simulation.step(&mut vec![&mut tracer], 1e-3);
+        }
+
+        assert!((tracer.pos().length() - 2.0).abs() < 1e-4);
+        assert!((simulation.time() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn conserves_energy_with_self_gravity() {
+        let mut simulation = External::new(BruteForce::new(1.0), Hernquist::new(10.0, 1.0, 1.0));
+        let mut bodies = vec![
+            Body::new(Vec2::new(1.0, 0.0), Vec2::new(0.0, 2.0), 0.1),
+            Body::new(Vec2::new(-2.0, 0.5), Vec2::new(0.3, -1.5), 0.2),
+        ];
+
+        let energy = |simulation: &External<_, _>, bodies: &[Body]| {
+            let pair = -bodies[0].mass() * bodies[1].mass() / bodies[0].pos().dist(bodies[1].pos());
+            kinetic_energy(bodies) + simulation.potential_energy(bodies) + pair
+        };
+        let initial_energy = energy(&simulation, &bodies);
+
+        for _ in 0..5_000 {
+            simulation.step(&mut bodies.iter_mut().collect(), 1e-3);
+        }
+
+        assert!(
+            (energy(&simulation, &bodies) - initial_energy).abs() < 1e-4 * initial_energy.abs()
+        );
+    }
+
+    #[test]
+    fn advances_time_dependent_potentials() {
+        let mut simulation =
+            External::new(BruteForce::new(1.0), RotatingBar::new(1.0, 1.0, 2.0)).with_time(1.0);
+        let mut body = Body::new(Vec2::new(1.0, 0.0), Vec2::zero(), 1.0);
+
+        simulation.step(&mut vec![&mut body], 0.5);
+
+        assert_eq!(simulation.time(), 1.5);
+        assert_eq!(simulation.potential().angle(simulation.time()), 3.0);
+    }
+}
@@ -0,0 +1,514 @@
+use super::{
+    barnes_hut::{quad::Quad, tree::BarnesHutTree},
+    Simulation,
+};
+use crate::{
+    body::Body,
+    complex::Complex,
+    force_law::{ForceLaw, PowerLaw},
+    vec2::Vec2,
+};
+
+/// Expansion order used unless `with_order` is given.
+pub const DEFAULT_ORDER: usize = 10;
+// Cells act through their expansions once their radii add up to less than
+// this fraction of the distance between their centers.
+const SEPARATION: f64 = 0.5;
+
+/// Fast multipole method for gravity confined to the plane, where the pair
+/// potential is `g m1 m2 ln r` as for `PowerLaw` with an exponent of 1.
+///
+/// This is not the Newtonian `r^-2` force the other solvers use: forces
+/// fall off as `1 / r`, so it cannot stand in for them in the same run.
+///
+/// Positions are complex numbers, so that the potential of a cell is the
+/// real part of a Laurent series around its center (Greengard & Rokhlin,
+/// 1987). Cells are the nodes of a `BarnesHutTree` over `quad`, which adapts
+/// to clustering, and pairs of cells are found by walking the tree against
+/// itself; bodies outside the quad are left alone.
+pub struct LogFmm {
+    quad: Quad,
+    g: f64,
+    order: usize,
+    potential_energy: f64,
+    max_leaf_interactions: usize,
+}
+
+impl LogFmm {
+    pub fn new(quad: Quad, g: f64) -> LogFmm {
+        LogFmm {
+            quad,
+            g,
+            order: DEFAULT_ORDER,
+            potential_energy: 0.0,
+            max_leaf_interactions: 0,
+        }
+    }
+
+    /// Number of terms kept in each expansion; the error falls roughly
+    /// geometrically with it.
+    pub fn with_order(mut self, order: usize) -> LogFmm {
+        self.order = order;
+        self
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn potential_energy(&self) -> f64 {
+        self.potential_energy
+    }
+
+    /// Most bodies and expansions any body interacted with directly in the
+    /// last step, on top of the local expansion of its leaf.
+    pub fn max_leaf_interactions(&self) -> usize {
+        self.max_leaf_interactions
+    }
+}
+
+// Node of the tree with the expansions around its center. Leaves hold one
+// body and are centered on it.
+struct Cell {
+    center: Complex,
+    radius: f64,
+    index: Option<usize>,
+    sources: usize,
+    children: Vec<usize>,
+    multipole: Vec<Complex>,
+    local: Vec<Complex>,
+}
+
+impl Cell {
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+// Appends `node` and its descendants to `cells` parents first, returning
+// the index of `node`.
+fn flatten(
+    node: &BarnesHutTree,
+    bodies: &[&mut Body],
+    order: usize,
+    cells: &mut Vec<Cell>,
+) -> usize {
+    let index = cells.len();
+    let (center, radius) = match node.index() {
+        Some(i) => (complex(bodies[i].pos()), 0.0),
+        None => (
+            complex(node.quad().center()),
+            node.quad().length() / 2.0_f64.sqrt(),
+        ),
+    };
+    cells.push(Cell {
+        center,
+        radius,
+        index: node.index(),
+        sources: 0,
+        children: Vec::new(),
+        multipole: vec![Complex::zero(); order + 1],
+        local: vec![Complex::zero(); order + 1],
+    });
+
+    let children: Vec<usize> = [
+        node.northwest(),
+        node.northeast(),
+        node.southwest(),
+        node.southeast(),
+    ]
+    .iter()
+    .flatten()
+    .map(|child| flatten(child, bodies, order, cells))
+    .collect();
+
+    cells[index].sources = match cells[index].index {
+        Some(i) if children.is_empty() => !bodies[i].is_test_particle() as usize,
+        _ => children.iter().map(|&child| cells[child].sources).sum(),
+    };
+    cells[index].children = children;
+
+    index
+}
+
+impl Simulation for LogFmm {
+    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        let order = self.order;
+        let law = PowerLaw::new(self.g, 1.0);
+        let binomials = binomials(2 * order);
+
+        // Test particles are leaves too, so that they find their local
+        // expansions, but they are no sources.
+        let mut tree = BarnesHutTree::new(self.quad.clone());
+        for (i, body) in bodies.iter().enumerate() {
+            if self.quad.contains(body.pos()) {
+                tree.insert_indexed(i, body);
+            }
+        }
+        let mut cells = Vec::new();
+        flatten(&tree, bodies, order, &mut cells);
+
+        // Upward pass: multipole expansions of the leaves, shifted to the
+        // centers of their parents.
+        for c in (0..cells.len()).rev() {
+            let cell = &cells[c];
+            let multipole = if cell.is_leaf() {
+                let mut multipole = vec![Complex::zero(); order + 1];
+                if cell.sources > 0 {
+                    multipole[0] = Complex::new(bodies[cell.index.unwrap()].mass(), 0.0);
+                }
+                multipole
+            } else {
+                let mut multipole = vec![Complex::zero(); order + 1];
+                for &child in cell
+                    .children
+                    .iter()
+                    .filter(|&&child| cells[child].sources > 0)
+                {
+                    let shifted = shift_multipole(
+                        &cells[child].multipole,
+                        cells[child].center - cell.center,
+                        &binomials,
+                    );
+                    for (coefficient, term) in multipole.iter_mut().zip(shifted) {
+                        *coefficient = *coefficient + term;
+                    }
+                }
+                multipole
+            };
+            cells[c].multipole = multipole;
+        }
+
+        // Walks the tree against itself, converting the multipole expansions
+        // of well separated cells into local ones and opening the larger
+        // cell of every other pair down to single bodies.
+        let mut near = vec![(Vec2::zero(), 0.0); bodies.len()];
+        let mut leaf_interactions = vec![0; cells.len()];
+        let mut pairs = if cells.is_empty() {
+            vec![]
+        } else {
+            vec![(0, 0)]
+        };
+        while let Some((target, source)) = pairs.pop() {
+            let (a, b) = (&cells[target], &cells[source]);
+            if b.sources == 0 {
+                continue;
+            }
+
+            if a.is_leaf() && b.is_leaf() {
+                let (i, j) = (a.index.unwrap(), b.index.unwrap());
+                if i != j {
+                    let (force, potential) = law.interact(bodies[i], bodies[j]);
+                    near[i] = (near[i].0.clone() + force, near[i].1 + potential);
+                    leaf_interactions[target] += 1;
+                }
+            } else if a.radius + b.radius < SEPARATION * (a.center - b.center).norm() {
+                if a.is_leaf() {
+                    let i = a.index.unwrap();
+                    let (force, potential) = evaluate_multipole(
+                        &b.multipole,
+                        complex(bodies[i].pos()) - b.center,
+                        self.g * bodies[i].mass(),
+                    );
+                    near[i] = (near[i].0.clone() + force, near[i].1 + potential);
+                    leaf_interactions[target] += 1;
+                } else {
+                    let converted =
+                        multipole_to_local(&b.multipole, b.center - a.center, &binomials);
+                    for (coefficient, term) in cells[target].local.iter_mut().zip(converted) {
+                        *coefficient = *coefficient + term;
+                    }
+                }
+            } else if b.is_leaf() || (!a.is_leaf() && a.radius >= b.radius) {
+                pairs.extend(a.children.iter().map(|&child| (child, source)));
+            } else {
+                pairs.extend(b.children.iter().map(|&child| (target, child)));
+            }
+        }
+
+        // Downward pass: local expansions shifted to the centers of the
+        // children, down to the bodies the leaves are centered on.
+        for c in 0..cells.len() {
+            for i in 0..cells[c].children.len() {
+                let child = cells[c].children[i];
+                let shifted = shift_local(
+                    &cells[c].local,
+                    cells[child].center - cells[c].center,
+                    &binomials,
+                );
+                for (coefficient, term) in cells[child].local.iter_mut().zip(shifted) {
+                    *coefficient = *coefficient + term;
+                }
+            }
+        }
+
+        let mut interactions = vec![None; bodies.len()];
+        for cell in cells.iter().filter(|cell| cell.is_leaf()) {
+            if let Some(i) = cell.index {
+                let coupling = self.g * bodies[i].mass();
+                let derivative = cell.local.get(1).copied().unwrap_or_else(Complex::zero);
+                let (force, potential) = near[i].clone();
+
+                interactions[i] = Some((
+                    force + Vec2::new(-derivative.re(), derivative.im()) * coupling,
+                    potential + coupling * cell.local[0].re(),
+                ));
+            }
+        }
+        self.max_leaf_interactions = leaf_interactions.into_iter().max().unwrap_or(0);
+
+        let mut potential_energy = 0.0;
+        for (body, interaction) in bodies.iter_mut().zip(interactions) {
+            body.reset_force();
+            if let Some((force, potential)) = interaction {
+                body.add_interaction(force, potential);
+                if !body.is_test_particle() {
+                    potential_energy += potential;
+                }
+                body.update(dt);
+            }
+        }
+
+        // Every pair of massive bodies is counted once from each side.
+        self.potential_energy = potential_energy / 2.0;
+    }
+}
+
+// Force and potential of a multipole expansion on a body `offset` from its
+// center, with `coupling` the body's mass times `g`.
+fn evaluate_multipole(multipole: &[Complex], offset: Complex, coupling: f64) -> (Vec2, f64) {
+    let inverse = Complex::new(1.0, 0.0) / offset;
+
+    let mut potential = multipole[0] * offset.ln();
+    let mut derivative = multipole[0] * inverse;
+    let mut power = inverse;
+    for (k, coefficient) in multipole.iter().enumerate().skip(1) {
+        potential = potential + *coefficient * power;
+        power = power * inverse;
+        derivative = derivative - *coefficient * power * k as f64;
+    }
+
+    (
+        Vec2::new(-derivative.re(), derivative.im()) * coupling,
+        coupling * potential.re(),
+    )
+}
+
+fn complex(pos: &Vec2) -> Complex {
+    Complex::new(pos.x(), pos.y())
+}
+
+// Pascal's triangle up to row `size`.
+fn binomials(size: usize) -> Vec<Vec<f64>> {
+    let mut rows: Vec<Vec<f64>> = vec![vec![1.0]];
+    for n in 1..=size {
+        let mut row = vec![1.0; n + 1];
+        for k in 1..n {
+            row[k] = rows[n - 1][k - 1] + rows[n - 1][k];
+        }
+        rows.push(row);
+    }
+
+    rows
+}
+
+fn powers(z: Complex, count: usize) -> Vec<Complex> {
+    let mut powers = vec![Complex::new(1.0, 0.0); count + 1];
+    for k in 1..=count {
+        powers[k] = powers[k - 1] * z;
+    }
+
+    powers
+}
+
+// Multipole expansion around a center `shift` away from the new one.
+fn shift_multipole(multipole: &[Complex], shift: Complex, binomials: &[Vec<f64>]) -> Vec<Complex> {
+    let order = multipole.len() - 1;
+    let shifts = powers(shift, order);
+
+    let mut shifted = vec![multipole[0]; order + 1];
+    for l in 1..=order {
+        shifted[l] = -(multipole[0] * shifts[l]) / l as f64;
+        for k in 1..=l {
+            shifted[l] = shifted[l] + multipole[k] * shifts[l - k] * binomials[l - 1][k - 1];
+        }
+    }
+
+    shifted
+}
+
+// Local expansion of a multipole expansion centered `offset` away.
+fn multipole_to_local(
+    multipole: &[Complex],
+    offset: Complex,
+    binomials: &[Vec<f64>],
+) -> Vec<Complex> {
+    let order = multipole.len() - 1;
+    let inverses = powers(Complex::new(1.0, 0.0) / offset, order);
+
+    // a_k (-1)^k / z0^k
+    let terms: Vec<Complex> = (1..=order)
+        .map(|k| multipole[k] * inverses[k] * if k % 2 == 0 { 1.0 } else { -1.0 })
+        .collect();
+
+    let mut local = vec![Complex::zero(); order + 1];
+    local[0] =
+        multipole[0] * (-offset).ln() + terms.iter().fold(Complex::zero(), |sum, &t| sum + t);
+    for l in 1..=order {
+        let sum = terms
+            .iter()
+            .enumerate()
+            .fold(Complex::zero(), |sum, (k, &term)| {
+                sum + term * binomials[l + k][k]
+            });
+        local[l] = (sum - multipole[0] / l as f64) * inverses[l];
+    }
+
+    local
+}
+
+// Local expansion re-centered `shift` away from its current center.
+fn shift_local(local: &[Complex], shift: Complex, binomials: &[Vec<f64>]) -> Vec<Complex> {
+    let order = local.len() - 1;
+    let shifts = powers(shift, order);
+
+    (0..=order)
+        .map(|l| {
+            (l..=order).fold(Complex::zero(), |sum, k| {
+                sum + local[k] * shifts[k - l] * binomials[k][l]
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::brute_force::BruteForce;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn bodies() -> Vec<Body> {
+        let mut rng = StdRng::seed_from_u64(13);
+
+        (0..600)
+            .map(|i| {
+                // Half of the bodies in a clump, to leave some leaves empty.
+                let pos = if i % 2 == 0 {
+                    Vec2::new(rng.gen_range(-10.0, 10.0), rng.gen_range(-10.0, 10.0))
+                } else {
+                    Vec2::new(rng.gen_range(3.0, 6.0), rng.gen_range(-7.0, -2.0))
+                };
+                Body::new(pos, Vec2::zero(), rng.gen_range(1.0, 3.0))
+                    .with_test_particle(i % 50 == 0)
+            })
+            .collect()
+    }
+
+    fn direct_bodies() -> Vec<Body> {
+        let mut bodies = bodies();
+        BruteForce::with_law(PowerLaw::new(1.5, 1.0)).step(&mut bodies.iter_mut().collect(), 0.0);
+        bodies
+    }
+
+    fn relative_error(order: usize, direct_bodies: &[Body]) -> (f64, f64) {
+        let mut bodies = bodies();
+        let mut simulation = LogFmm::new(Quad::new(Vec2::zero(), 20.0), 1.5).with_order(order);
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        let mut squared_error = 0.0;
+        let mut squared_force = 0.0;
+        let mut potential_error: f64 = 0.0;
+        for (body, direct_body) in bodies.iter().zip(direct_bodies) {
+            squared_error += body.force().dist(direct_body.force()).powi(2);
+            squared_force += direct_body.force().length().powi(2);
+            potential_error = potential_error.max(
+                ((body.potential() - direct_body.potential()) / direct_body.potential()).abs(),
+            );
+        }
+
+        ((squared_error / squared_force).sqrt(), potential_error)
+    }
+
+    #[test]
+    fn reduces_error_with_expansion_order() {
+        let direct_bodies = direct_bodies();
+        let errors: Vec<(f64, f64)> = [2, 4, 8, 16]
+            .iter()
+            .map(|&order| relative_error(order, &direct_bodies))
+            .collect();
+
+        for pair in errors.windows(2) {
+            assert!(pair[1].0 < pair[0].0 / 4.0, "{:?}", errors);
+            assert!(pair[1].1 < pair[0].1, "{:?}", errors);
+        }
+        assert!(errors[3].0 < 1e-8, "{:?}", errors);
+        assert!(errors[3].1 < 1e-9, "{:?}", errors);
+    }
+
+    #[test]
+    fn bounds_leaf_cost_for_clustered_bodies() {
+        // Nine in ten bodies in a clump a thousand times smaller than the
+        // rest, which a uniform grid would put in a single leaf.
+        let clustered = |clump: f64| {
+            let mut rng = StdRng::seed_from_u64(3);
+            (0..2000)
+                .map(|i| {
+                    let size = if i % 10 == 0 { 10.0 } else { 10.0 * clump };
+                    let pos = Vec2::new(rng.gen_range(-size, size), rng.gen_range(-size, size));
+                    Body::new(pos, Vec2::zero(), rng.gen_range(1.0, 3.0))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut max_leaf_interactions = Vec::new();
+        for &clump in [1.0, 1e-3].iter() {
+            let mut bodies = clustered(clump);
+            let mut direct_bodies = bodies.clone();
+            BruteForce::with_law(PowerLaw::new(1.5, 1.0))
+                .step(&mut direct_bodies.iter_mut().collect(), 0.0);
+
+            let mut simulation = LogFmm::new(Quad::new(Vec2::zero(), 20.0), 1.5);
+            simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+            max_leaf_interactions.push(simulation.max_leaf_interactions());
+
+            let mut squared_error = 0.0;
+            let mut squared_force = 0.0;
+            for (body, direct_body) in bodies.iter().zip(&direct_bodies) {
+                squared_error += body.force().dist(direct_body.force()).powi(2);
+                squared_force += direct_body.force().length().powi(2);
+            }
+            assert!((squared_error / squared_force).sqrt() < 1e-5);
+        }
+
+        assert!(
+            max_leaf_interactions[0] < 100,
+            "{:?}",
+            max_leaf_interactions
+        );
+        assert!(
+            max_leaf_interactions[1] < 2 * max_leaf_interactions[0],
+            "{:?}",
+            max_leaf_interactions
+        );
+    }
+
+    #[test]
+    fn calculates_potential_energy() {
+        let direct_bodies = direct_bodies();
+        let direct_potential_energy = direct_bodies
+            .iter()
+            .filter(|b| !b.is_test_particle())
+            .map(|b| b.potential())
+            .sum::<f64>()
+            / 2.0;
+
+        let mut bodies = bodies();
+        let mut simulation = LogFmm::new(Quad::new(Vec2::zero(), 20.0), 1.5);
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        let relative_error = ((simulation.potential_energy() - direct_potential_energy)
+            / direct_potential_energy)
+            .abs();
+        assert!(relative_error < 1e-6, "relative error: {}", relative_error);
+    }
+}
@@ -2,7 +2,17 @@ use crate::body::Body;
 
 pub mod barnes_hut;
 pub mod brute_force;
+pub mod collisional;
+pub mod external;
+pub mod fmm;
+pub mod particle_mesh;
+pub mod periodic;
+pub mod tree_pm;
 
+/// Advances bodies by one time step. `BruteForce`, `BarnesHut`,
+/// `ParticleMesh` and `TreePm` compute Newtonian gravity by default, while
+/// `fmm::LogFmm` uses the logarithmic potential of gravity in two
+/// dimensions.
 pub trait Simulation {
     fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64);
 }
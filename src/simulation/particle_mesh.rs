@@ -0,0 +1,401 @@
+use super::Simulation;
+use crate::{
+    body::Body,
+    complex::Complex,
+    fft::fft2,
+    periodic::PeriodicBox,
+    simulation::barnes_hut::quad::Quad,
+    special::{erf, erfc},
+    vec2::Vec2,
+};
+use std::f64::consts::PI;
+
+/// Gravity from a potential solved on a square mesh with FFTs.
+///
+/// Masses are spread over the mesh by cloud-in-cell assignment and
+/// accelerations are interpolated back the same way from central
+/// differences of the potential, so bodies exert no force on themselves.
+/// Forces are smoothed on the scale of a cell and no potential energy is
+/// computed.
+pub struct ParticleMesh {
+    quad: Quad,
+    resolution: usize,
+    periodic: bool,
+    g: f64,
+    split_radius: Option<f64>,
+    green: Vec<Complex>,
+}
+
+impl ParticleMesh {
+    /// Mesh of `resolution` cells per side covering a periodic box, which
+    /// must be a power of two.
+    pub fn new(periodic_box: PeriodicBox, resolution: usize, g: f64) -> ParticleMesh {
+        assert!(
+            resolution.is_power_of_two(),
+            "resolution must be a power of two"
+        );
+
+        let mut mesh = ParticleMesh {
+            quad: periodic_box.quad(),
+            resolution,
+            periodic: true,
+            g,
+            split_radius: None,
+            green: Vec::new(),
+        };
+        mesh.green = mesh.green();
+        mesh
+    }
+
+    /// Mesh of `resolution` cells per side covering `quad`, zero-padded to
+    /// twice its size so that there are no periodic images. The resolution
+    /// must be a power of two. Bodies outside the quad neither exert nor
+    /// receive forces.
+    pub fn isolated(quad: Quad, resolution: usize, g: f64) -> ParticleMesh {
+        assert!(
+            resolution.is_power_of_two(),
+            "resolution must be a power of two"
+        );
+
+        let mut mesh = ParticleMesh {
+            quad,
+            resolution,
+            periodic: false,
+            g,
+            split_radius: None,
+            green: Vec::new(),
+        };
+        mesh.green = mesh.green();
+        mesh
+    }
+
+    /// Keeps only the long-range part `erf(r / 2 split_radius) / r` of each
+    /// interaction, leaving the rest to a short-range solver.
+    pub fn with_split_radius(mut self, split_radius: f64) -> ParticleMesh {
+        self.split_radius = Some(split_radius);
+        self.green = self.green();
+        self
+    }
+
+    pub fn quad(&self) -> &Quad {
+        &self.quad
+    }
+
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    pub fn is_periodic(&self) -> bool {
+        self.periodic
+    }
+
+    pub fn split_radius(&self) -> Option<f64> {
+        self.split_radius
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.quad.length() / self.resolution as f64
+    }
+
+    /// Accelerations at the positions of `bodies` from the mass of the
+    /// bodies themselves, leaving test particles out as sources.
+    pub fn accelerations(&self, bodies: &[&mut Body]) -> Vec<Vec2> {
+        let size = self.mesh_size();
+        let mut mesh = vec![Complex::zero(); size * size];
+
+        for body in bodies {
+            if body.is_test_particle() || !self.quad.contains(body.pos()) {
+                continue;
+            }
+
+            for (index, weight) in self.cloud(body.pos()) {
+                mesh[index] = mesh[index] + Complex::new(body.mass() * weight, 0.0);
+            }
+        }
+
+        fft2(&mut mesh, size, false);
+        for (value, green) in mesh.iter_mut().zip(&self.green) {
+            *value = *value * *green;
+        }
+        fft2(&mut mesh, size, true);
+
+        let potential: Vec<f64> = mesh.iter().map(|value| value.re()).collect();
+        let cell_size = self.cell_size();
+        let at = |i: isize, j: isize| {
+            let wrap = |i: isize| i.rem_euclid(size as isize) as usize;
+            potential[wrap(j) * size + wrap(i)]
+        };
+
+        bodies
+            .iter()
+            .map(|body| {
+                if !self.quad.contains(body.pos()) {
+                    return Vec2::zero();
+                }
+
+                self.cloud_nodes(body.pos()).iter().fold(
+                    Vec2::zero(),
+                    |acceleration, &((i, j), weight)| {
+                        let gradient =
+                            Vec2::new(at(i + 1, j) - at(i - 1, j), at(i, j + 1) - at(i, j - 1))
+                                / (2.0 * cell_size);
+
+                        acceleration - gradient * weight
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn mesh_size(&self) -> usize {
+        if self.periodic {
+            self.resolution
+        } else {
+            2 * self.resolution
+        }
+    }
+
+    // Mesh nodes at cell centers around `pos` with their cloud-in-cell
+    // weights.
+    fn cloud_nodes(&self, pos: &Vec2) -> [((isize, isize), f64); 4] {
+        let cell_size = self.cell_size();
+        let corner = self.quad.center().clone() - Vec2::unit() * (self.quad.length() / 2.0);
+        let u = (pos.x() - corner.x()) / cell_size - 0.5;
+        let v = (pos.y() - corner.y()) / cell_size - 0.5;
+
+        let (i, j) = (u.floor(), v.floor());
+        let (tx, ty) = (u - i, v - j);
+        let (i, j) = (i as isize, j as isize);
+
+        [
+            ((i, j), (1.0 - tx) * (1.0 - ty)),
+            ((i + 1, j), tx * (1.0 - ty)),
+            ((i, j + 1), (1.0 - tx) * ty),
+            ((i + 1, j + 1), tx * ty),
+        ]
+    }
+
+    fn cloud(&self, pos: &Vec2) -> Vec<(usize, f64)> {
+        let size = self.mesh_size() as isize;
+
+        self.cloud_nodes(pos)
+            .iter()
+            .map(|&((i, j), weight)| {
+                (
+                    (j.rem_euclid(size) * size + i.rem_euclid(size)) as usize,
+                    weight,
+                )
+            })
+            .collect()
+    }
+
+    fn green(&self) -> Vec<Complex> {
+        if self.periodic {
+            self.periodic_green()
+        } else {
+            self.isolated_green()
+        }
+    }
+
+    // Fourier transform of `-g / r`, or its long-range part, for a unit mass
+    // on the periodic mesh, divided by the smoothing of assignment and
+    // interpolation.
+    fn periodic_green(&self) -> Vec<Complex> {
+        let size = self.resolution;
+        let cell_size = self.cell_size();
+        let wave_number = |i: usize| {
+            let i = if i < size / 2 {
+                i as f64
+            } else {
+                i as f64 - size as f64
+            };
+            2.0 * PI * i / self.quad.length()
+        };
+        let window = |k: f64| {
+            let x = k * cell_size / 2.0;
+            if x == 0.0 {
+                1.0
+            } else {
+                x.sin() / x
+            }
+        };
+
+        let mut green = vec![Complex::zero(); size * size];
+        for j in 0..size {
+            for i in 0..size {
+                if i == 0 && j == 0 {
+                    continue;
+                }
+
+                let (kx, ky) = (wave_number(i), wave_number(j));
+                let k = kx.hypot(ky);
+                let smoothing = (window(kx) * window(ky)).powi(4);
+                let long_range = self.split_radius.map_or(1.0, |r| erfc(k * r));
+
+                green[j * size + i] = Complex::new(
+                    -2.0 * PI * self.g * long_range / k / cell_size.powi(2) / smoothing,
+                    0.0,
+                );
+            }
+        }
+
+        green
+    }
+
+    // Transformed `-g / r`, or its long-range part, sampled on the padded
+    // mesh. The full kernel is averaged over the cell at zero separation.
+    fn isolated_green(&self) -> Vec<Complex> {
+        let size = self.mesh_size();
+        let cell_size = self.cell_size();
+        let offset = |i: usize| {
+            if i < size / 2 {
+                i as f64
+            } else {
+                i as f64 - size as f64
+            }
+        };
+
+        let mut green = vec![Complex::zero(); size * size];
+        for j in 0..size {
+            for i in 0..size {
+                let dist = offset(i).hypot(offset(j)) * cell_size;
+                let potential = match self.split_radius {
+                    Some(r) if dist == 0.0 => -self.g / (r * PI.sqrt()),
+                    Some(r) => -self.g * erf(dist / (2.0 * r)) / dist,
+                    None if dist == 0.0 => -self.g * 4.0 * (1.0 + 2f64.sqrt()).ln() / cell_size,
+                    None => -self.g / dist,
+                };
+
+                green[j * size + i] = Complex::new(potential, 0.0);
+            }
+        }
+
+        fft2(&mut green, size, false);
+        green
+    }
+}
+
+impl Simulation for ParticleMesh {
+    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        if self.periodic {
+            PeriodicBox::new(self.quad.center().clone(), self.quad.length()).wrap_bodies(bodies);
+        }
+
+        let accelerations = self.accelerations(bodies);
+
+        for (body, acceleration) in bodies.iter_mut().zip(accelerations) {
+            body.reset_force();
+            if self.quad.contains(body.pos()) {
+                let mass = body.mass();
+                body.add_interaction(acceleration * mass, 0.0);
+                body.update(dt);
+            }
+        }
+
+        if self.periodic {
+            PeriodicBox::new(self.quad.center().clone(), self.quad.length()).wrap_bodies(bodies);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::brute_force::BruteForce;
+
+    #[test]
+    fn matches_analytic_potential_of_density_wave() {
+        let size = 32;
+        let length = 4.0;
+        let amplitude = 0.3;
+        let wave_number = 2.0 * PI * 2.0 / length;
+
+        let cell_size = length / size as f64;
+        let mut bodies: Vec<Body> = (0..size * size)
+            .map(|n| {
+                let pos = Vec2::new((n % size) as f64 + 0.5, (n / size) as f64 + 0.5) * cell_size;
+                let mass = cell_size.powi(2) * (1.0 + amplitude * (wave_number * pos.x()).cos());
+                Body::new(pos, Vec2::zero(), mass)
+            })
+            .collect();
+
+        let mut simulation = ParticleMesh::new(
+            PeriodicBox::new(Vec2::unit() * (length / 2.0), length),
+            size,
+            1.0,
+        );
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        // A sheet with surface density 1 + A cos(kx) has the potential
+        // -2π A cos(kx) / k.
+        for body in &bodies {
+            let expected = -2.0 * PI * amplitude * (wave_number * body.pos().x()).sin();
+            let acceleration = body.force().x() / body.mass();
+
+            assert!((acceleration - expected).abs() < 2e-2 * 2.0 * PI * amplitude);
+            assert!(body.force().y().abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn approximates_direct_summation_for_smooth_distributions() {
+        // A Gaussian sheet sampled on a lattice, probed between lattice
+        // points where the nearest bodies pull in opposite directions.
+        let spacing = 0.125;
+        let mut bodies: Vec<Body> = (0..64 * 64)
+            .map(|n| {
+                let pos = (Vec2::new((n % 64) as f64, (n / 64) as f64) + Vec2::unit() * 0.5)
+                    * spacing
+                    - Vec2::unit() * 4.0;
+                let mass = (-pos.dot(&pos) / 2.0).exp() * spacing.powi(2) / (2.0 * PI);
+                Body::new(pos, Vec2::zero(), mass)
+            })
+            .collect();
+        let massive = bodies.len();
+
+        for &(x, y) in [(2.0, 0.0), (-2.5, 1.0), (1.5, -1.5), (3.0, 2.0), (0.0, 3.5)].iter() {
+            bodies.push(Body::new(Vec2::new(x, y), Vec2::zero(), 1.0).with_test_particle(true));
+        }
+
+        let mut direct_bodies = bodies.clone();
+        BruteForce::new(1.0).step(&mut direct_bodies.iter_mut().collect(), 0.0);
+
+        let mut simulation = ParticleMesh::isolated(Quad::new(Vec2::zero(), 16.0), 128, 1.0);
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        for (body, direct_body) in bodies.iter().zip(&direct_bodies).skip(massive) {
+            let error = body.force().dist(direct_body.force()) / direct_body.force().length();
+            assert!(error < 1e-2, "relative error: {}", error);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "resolution must be a power of two")]
+    fn rejects_periodic_resolution_other_than_power_of_two() {
+        ParticleMesh::new(PeriodicBox::new(Vec2::zero(), 1.0), 48, 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "resolution must be a power of two")]
+    fn rejects_isolated_resolution_other_than_power_of_two() {
+        ParticleMesh::isolated(Quad::new(Vec2::zero(), 1.0), 48, 1.0);
+    }
+
+    #[test]
+    fn conserves_momentum() {
+        let mut bodies = [
+            Body::new(Vec2::new(0.3, 0.4), Vec2::zero(), 2.0),
+            Body::new(Vec2::new(-1.2, 0.9), Vec2::zero(), 1.0),
+            Body::new(Vec2::new(0.7, -1.5), Vec2::zero(), 3.0),
+        ];
+
+        let mut simulation = ParticleMesh::isolated(Quad::new(Vec2::zero(), 4.0), 32, 1.0);
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        let total_force = bodies
+            .iter()
+            .fold(Vec2::zero(), |sum, b| sum + b.force().clone());
+        assert!(total_force.length() < 1e-10);
+        assert!(bodies[0].force().x() < 0.0);
+    }
+}
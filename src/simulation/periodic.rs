@@ -0,0 +1,141 @@
+use super::Simulation;
+use crate::{body::Body, periodic::PeriodicBox};
+
+/// Wraps bodies back into a periodic box after each step of another
+/// simulation. Its force law should use nearest images, such as `Ewald` or
+/// `MinimumImage`.
+pub struct Periodic<S: Simulation> {
+    simulation: S,
+    periodic_box: PeriodicBox,
+}
+
+impl<S: Simulation> Periodic<S> {
+    pub fn new(simulation: S, periodic_box: PeriodicBox) -> Periodic<S> {
+        Periodic {
+            simulation,
+            periodic_box,
+        }
+    }
+
+    pub fn simulation(&self) -> &S {
+        &self.simulation
+    }
+
+    pub fn periodic_box(&self) -> &PeriodicBox {
+        &self.periodic_box
+    }
+}
+
+impl<S: Simulation> Simulation for Periodic<S> {
+    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        self.simulation.step(bodies, dt);
+        self.periodic_box.wrap_bodies(bodies);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        periodic::ewald::Ewald,
+        simulation::{barnes_hut::BarnesHut, brute_force::BruteForce},
+        vec2::Vec2,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // See `ewald::tests::SQUARE_LATTICE_SUM`.
+    const SQUARE_LATTICE_SUM: f64 = -3.900_264_920_001_955;
+
+    // Unit masses on a square lattice with unit spacing, 4 to a box.
+    fn lattice() -> (PeriodicBox, Vec<Body>) {
+        let periodic_box = PeriodicBox::new(Vec2::zero(), 2.0);
+        let bodies = [(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)]
+            .iter()
+            .map(|&(x, y)| Body::new(Vec2::new(x, y), Vec2::zero(), 1.0))
+            .collect();
+
+        (periodic_box, bodies)
+    }
+
+    #[test]
+    fn wraps_bodies_leaving_the_box() {
+        let periodic_box = PeriodicBox::new(Vec2::zero(), 2.0);
+        let mut simulation = Periodic::new(
+            BruteForce::with_law(Ewald::new(periodic_box.clone(), 1.0)),
+            periodic_box,
+        );
+        let mut body = Body::new(Vec2::new(0.9, 0.0), Vec2::new(1.0, 0.0), 1.0);
+
+        simulation.step(&mut vec![&mut body], 0.2);
+
+        assert!((body.pos().x() + 0.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn matches_square_lattice_energy() {
+        let (periodic_box, mut bodies) = lattice();
+        let ewald = Ewald::new(periodic_box.clone(), 1.0);
+        let self_potential = ewald.self_potential();
+
+        let mut simulation = Periodic::new(BruteForce::with_law(ewald), periodic_box.clone());
+        simulation.step(&mut bodies.iter_mut().collect(), 0.0);
+
+        let mut tree_bodies = lattice().1;
+        let mut tree_simulation = BarnesHut::with_law(
+            periodic_box.quad(),
+            Ewald::new(periodic_box.clone(), 1.0).with_table(64),
+        )
+        .with_periodic_box(periodic_box);
+        tree_simulation.step(&mut tree_bodies.iter_mut().collect(), 0.0);
+
+        for body in bodies.iter().chain(&tree_bodies) {
+            // Each body's share of the energy is half its potential.
+            let energy = body.potential() - self_potential;
+            assert!((energy + SQUARE_LATTICE_SUM).abs() < 1e-4, "{}", energy);
+            assert!(body.force().length() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn approximates_direct_ewald_summation_with_tree() {
+        let periodic_box = PeriodicBox::new(Vec2::new(5.0, 5.0), 10.0);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // Two compact clumps, so the tree error comes only from their size,
+        // probed by test particles away from them.
+        let mut bodies = Vec::new();
+        for &(x, y) in [(2.5, 2.5), (7.0, 6.0)].iter() {
+            for _ in 0..15 {
+                let offset = Vec2::new(rng.gen_range(-0.1, 0.1), rng.gen_range(-0.1, 0.1));
+                bodies.push(Body::new(
+                    Vec2::new(x, y) + offset,
+                    Vec2::zero(),
+                    rng.gen_range(1.0, 2.0),
+                ));
+            }
+        }
+        while bodies.len() < 40 {
+            let pos = Vec2::new(rng.gen_range(0.0, 10.0), rng.gen_range(0.0, 10.0));
+            if bodies[..30].iter().all(|b| b.pos().dist(&pos) > 2.0) {
+                bodies.push(Body::new(pos, Vec2::zero(), 1.0).with_test_particle(true));
+            }
+        }
+
+        let mut direct_bodies = bodies.clone();
+        BruteForce::with_law(Ewald::new(periodic_box.clone(), 1.0))
+            .step(&mut direct_bodies.iter_mut().collect(), 0.0);
+
+        let mut tree_bodies = bodies;
+        BarnesHut::with_law(
+            periodic_box.quad(),
+            Ewald::new(periodic_box.clone(), 1.0).with_table(64),
+        )
+        .with_periodic_box(periodic_box)
+        .step(&mut tree_bodies.iter_mut().collect(), 0.0);
+
+        for (body, direct_body) in tree_bodies.iter().zip(&direct_bodies).skip(30) {
+            let error = body.force().dist(direct_body.force()) / direct_body.force().length();
+            assert!(error < 1e-2, "relative error: {}", error);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use super::{
+    barnes_hut::{quad::Quad, tree::BarnesHutTree},
+    particle_mesh::ParticleMesh,
+    Simulation,
+};
+use crate::{
+    body::Body,
+    force_law::{ForceLaw, MultipoleExpansion},
+    periodic::PeriodicBox,
+    special::erfc,
+    vec2::Vec2,
+};
+use std::f64::consts::PI;
+
+// Split radius in mesh cells, and the distance in split radii beyond which
+// the short-range force is dropped, where it is below 0.05% of Newtonian.
+const SPLIT_CELLS: f64 = 1.25;
+const CUTOFF_SPLITS: f64 = 5.0;
+// The short-range walk only reaches nodes comparable to the cutoff, so it
+// can afford to open them more eagerly than a full walk.
+const SHORT_RANGE_OPENING_ANGLE: f64 = 0.5;
+
+/// Newtonian gravity screened by `erfc(r / 2 split_radius)`, the part of
+/// the force left over by a mesh with the same split radius.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ShortRange {
+    g: f64,
+    split_radius: f64,
+}
+
+impl ShortRange {
+    pub fn new(g: f64, split_radius: f64) -> ShortRange {
+        ShortRange { g, split_radius }
+    }
+}
+
+impl ForceLaw for ShortRange {
+    fn interact(&self, body: &Body, other: &Body) -> (Vec2, f64) {
+        let diff = other.pos().clone() - body.pos().clone();
+        let dist = diff.length();
+        let x = dist / (2.0 * self.split_radius);
+
+        let coupling = self.g * body.mass() * other.mass();
+        let screening = erfc(x) + 2.0 * x / PI.sqrt() * (-x * x).exp();
+
+        (
+            diff * (coupling * screening / dist.powi(3)),
+            -coupling * erfc(x) / dist,
+        )
+    }
+}
+
+// Nodes are still replaced by their centers of mass, which is accurate as
+// long as they are small next to the split radius.
+impl MultipoleExpansion for ShortRange {}
+
+/// Long-range forces from a particle mesh and short-range forces from a
+/// Barnes-Hut walk limited to a few split radii around each body.
+pub struct TreePm {
+    mesh: ParticleMesh,
+    law: ShortRange,
+    cutoff: f64,
+}
+
+impl TreePm {
+    /// Periodic box with a mesh of `resolution` cells per side.
+    pub fn new(periodic_box: PeriodicBox, resolution: usize, g: f64) -> TreePm {
+        TreePm::with_mesh(ParticleMesh::new(periodic_box, resolution, g), g)
+    }
+
+    /// Isolated bodies in `quad`; bodies outside it are left alone.
+    pub fn isolated(quad: Quad, resolution: usize, g: f64) -> TreePm {
+        TreePm::with_mesh(ParticleMesh::isolated(quad, resolution, g), g)
+    }
+
+    fn with_mesh(mesh: ParticleMesh, g: f64) -> TreePm {
+        let split_radius = SPLIT_CELLS * mesh.cell_size();
+
+        TreePm {
+            mesh: mesh.with_split_radius(split_radius),
+            law: ShortRange::new(g, split_radius),
+            cutoff: CUTOFF_SPLITS * split_radius,
+        }
+    }
+
+    pub fn mesh(&self) -> &ParticleMesh {
+        &self.mesh
+    }
+
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff
+    }
+
+    fn periodic_box(&self) -> Option<PeriodicBox> {
+        let quad = self.mesh.quad();
+
+        if self.mesh.is_periodic() {
+            Some(PeriodicBox::new(quad.center().clone(), quad.length()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Simulation for TreePm {
+    fn step(&mut self, bodies: &mut Vec<&mut Body>, dt: f64) {
+        let periodic_box = self.periodic_box();
+        if let Some(periodic_box) = &periodic_box {
+            periodic_box.wrap_bodies(bodies);
+        }
+
+        let quad = self.mesh.quad().clone();
+        let accelerations = self.mesh.accelerations(bodies);
+
+        let mut tree = BarnesHutTree::new(quad.clone());
+        for body in bodies.iter() {
+            if !body.is_test_particle() && quad.contains(body.pos()) {
+                tree.insert(body);
+            }
+        }
+
+        for (body, acceleration) in bodies.iter_mut().zip(accelerations) {
+            body.reset_force();
+            if quad.contains(body.pos()) {
+                let mass = body.mass();
+                body.add_interaction(acceleration * mass, 0.0);
+                tree.update_short_range_force(
+                    body,
+                    &self.law,
+                    SHORT_RANGE_OPENING_ANGLE,
+                    self.cutoff,
+                    periodic_box.as_ref(),
+                );
+                body.update(dt);
+            }
+        }
+
+        if let Some(periodic_box) = &periodic_box {
+            periodic_box.wrap_bodies(bodies);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        force_law::Newtonian,
+        ic::{plummer::Plummer, Generator},
+        periodic::ewald::Ewald,
+        simulation::brute_force::BruteForce,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn relative_error(bodies: &[Body], direct_bodies: &[Body]) -> f64 {
+        let mut squared_error = 0.0;
+        let mut squared_force = 0.0;
+        for (body, direct_body) in bodies.iter().zip(direct_bodies) {
+            squared_error += body.force().dist(direct_body.force()).powi(2);
+            squared_force += direct_body.force().length().powi(2);
+        }
+
+        (squared_error / squared_force).sqrt()
+    }
+
+    #[test]
+    fn splits_newtonian_force() {
+        let law = ShortRange::new(1.5, 0.4);
+        let body = Body::new(Vec2::new(0.1, 0.2), Vec2::zero(), 2.0);
+        let other = Body::new(Vec2::new(0.6, -0.3), Vec2::zero(), 3.0);
+
+        let h = 1e-6;
+        let potential_at = |offset: Vec2| {
+            let mut moved = body.clone();
+            moved.translate(offset);
+            law.interact(&moved, &other).1
+        };
+        let gradient = Vec2::new(
+            (potential_at(Vec2::new(h, 0.0)) - potential_at(Vec2::new(-h, 0.0))) / (2.0 * h),
+            (potential_at(Vec2::new(0.0, h)) - potential_at(Vec2::new(0.0, -h))) / (2.0 * h),
+        );
+        let (force, potential) = law.interact(&body, &other);
+        assert!((force.clone() + gradient).length() < 1e-6 * force.length());
+
+        let (newtonian_force, newtonian_potential) = Newtonian::new(1.5).interact(&body, &other);
+        assert!(force.length() < newtonian_force.length());
+        assert!(potential > newtonian_potential);
+
+        let far = Body::new(Vec2::new(5.0, 0.0), Vec2::zero(), 3.0);
+        assert!(law.interact(&body, &far).0.length() < 1e-6);
+    }
+
+    #[test]
+    fn resolves_close_encounters_better_than_mesh() {
+        let bodies: Vec<Body> = Plummer::new(400, 1.0, 1.0, 1.0)
+            .with_seed(3)
+            .generate()
+            .into_iter()
+            .filter(|b| b.pos().length() < 7.0)
+            .collect();
+
+        let mut direct_bodies = bodies.clone();
+        BruteForce::new(1.0).step(&mut direct_bodies.iter_mut().collect(), 0.0);
+
+        let quad = Quad::new(Vec2::zero(), 16.0);
+        let mut mesh_bodies = bodies.clone();
+        ParticleMesh::isolated(quad.clone(), 64, 1.0)
+            .step(&mut mesh_bodies.iter_mut().collect(), 0.0);
+
+        let mut tree_pm_bodies = bodies;
+        TreePm::isolated(quad, 64, 1.0).step(&mut tree_pm_bodies.iter_mut().collect(), 0.0);
+
+        let error = relative_error(&tree_pm_bodies, &direct_bodies);
+        assert!(error < 1e-2, "relative error: {}", error);
+        assert!(error < relative_error(&mesh_bodies, &direct_bodies) / 5.0);
+    }
+
+    #[test]
+    fn matches_ewald_summation_in_periodic_box() {
+        let periodic_box = PeriodicBox::new(Vec2::zero(), 8.0);
+        let mut rng = StdRng::seed_from_u64(9);
+        let bodies: Vec<Body> = (0..200)
+            .map(|_| {
+                Body::new(
+                    Vec2::new(rng.gen_range(-4.0, 4.0), rng.gen_range(-4.0, 4.0)),
+                    Vec2::zero(),
+                    rng.gen_range(0.5, 1.5),
+                )
+            })
+            .collect();
+
+        let mut direct_bodies = bodies.clone();
+        BruteForce::with_law(Ewald::new(periodic_box.clone(), 1.0).with_table(64))
+            .step(&mut direct_bodies.iter_mut().collect(), 0.0);
+
+        let mut tree_pm_bodies = bodies;
+        TreePm::new(periodic_box, 64, 1.0).step(&mut tree_pm_bodies.iter_mut().collect(), 0.0);
+
+        let error = relative_error(&tree_pm_bodies, &direct_bodies);
+        assert!(error < 1e-2, "relative error: {}", error);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{body::Body, vec2::Vec2};
+use std::io::{self, BufRead, Write};
+
+/// State of a run at `time`, with the seed its initial conditions were
+/// generated from when there is one.
+///
+/// Snapshots are written as plain text, one `key values...` record per line:
+/// `seed`, `time` and one `body x y vx vy mass radius charge` line per body,
+/// followed by `test` for test particles.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Snapshot {
+    seed: Option<u64>,
+    time: f64,
+    bodies: Vec<Body>,
+}
+
+impl Snapshot {
+    pub fn new(time: f64, bodies: Vec<Body>) -> Snapshot {
+        Snapshot {
+            seed: None,
+            time,
+            bodies,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Snapshot {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn bodies(&self) -> &[Body] {
+        &self.bodies
+    }
+
+    pub fn into_bodies(self) -> Vec<Body> {
+        self.bodies
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if let Some(seed) = self.seed {
+            writeln!(writer, "seed {}", seed)?;
+        }
+        writeln!(writer, "time {}", self.time)?;
+
+        for body in &self.bodies {
+            writeln!(
+                writer,
+                "body {} {} {} {} {} {} {}{}",
+                body.pos().x(),
+                body.pos().y(),
+                body.velocity().x(),
+                body.velocity().y(),
+                body.mass(),
+                body.radius(),
+                body.charge(),
+                if body.is_test_particle() { " test" } else { "" }
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Snapshot> {
+        let mut snapshot = Snapshot::new(0.0, Vec::new());
+
+        for line in reader.lines() {
+            let line = line?;
+            let mut fields = line.split_whitespace();
+
+            match fields.next() {
+                Some("seed") => snapshot.seed = Some(parse(fields.next(), &line)?),
+                Some("time") => snapshot.time = parse(fields.next(), &line)?,
+                Some("body") => {
+                    let mut values = [0.0; 7];
+                    for value in values.iter_mut() {
+                        *value = parse(fields.next(), &line)?;
+                    }
+                    let [x, y, vx, vy, mass, radius, charge] = values;
+                    let test_particle = match fields.next() {
+                        Some("test") => true,
+                        None => false,
+                        Some(_) => return Err(invalid(&line)),
+                    };
+
+                    snapshot.bodies.push(
+                        Body::new(Vec2::new(x, y), Vec2::new(vx, vy), mass)
+                            .with_radius(radius)
+                            .with_charge(charge)
+                            .with_test_particle(test_particle),
+                    );
+                }
+                None => {}
+                Some(_) => return Err(invalid(&line)),
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+fn parse<T: std::str::FromStr>(field: Option<&str>, line: &str) -> io::Result<T> {
+    field
+        .and_then(|f| f.parse().ok())
+        .ok_or_else(|| invalid(line))
+}
+
+fn invalid(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid snapshot line: {}", line),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ic::{plummer::Plummer, Generator},
+        simulation::{
+            barnes_hut::{quad::Quad, BarnesHut},
+            Simulation,
+        },
+        units::G,
+    };
+
+    fn run(seed: u64) -> Snapshot {
+        let plummer = Plummer::new(200, 1e30, 1e12, G).with_seed(seed);
+        let mut bodies = plummer.generate();
+
+        let mut simulation = BarnesHut::new(Quad::new(Vec2::zero(), 1e14), G);
+        for _ in 0..10 {
+            simulation.step(&mut bodies.iter_mut().collect(), 1e5);
+        }
+
+        Snapshot::new(1e6, bodies).with_seed(plummer.seed())
+    }
+
+    #[test]
+    fn reproduces_runs_with_same_seed() {
+        assert_eq!(run(11), run(11));
+        assert_ne!(run(11).bodies(), run(12).bodies());
+    }
+
+    #[test]
+    fn writes_and_reads_back() {
+        let snapshot = Snapshot::new(
+            2.5,
+            vec![
+                Body::new(Vec2::new(0.1, -3e20), Vec2::new(1.0 / 3.0, 0.0), 1e30),
+                Body::new(Vec2::zero(), Vec2::unit(), 2.0)
+                    .with_radius(0.5)
+                    .with_charge(-1.25),
+                Body::new(Vec2::unit(), Vec2::zero(), 1.0).with_test_particle(true),
+            ],
+        )
+        .with_seed(42);
+
+        let mut buffer = Vec::new();
+        snapshot.write(&mut buffer).unwrap();
+
+        assert_eq!(Snapshot::read(&buffer[..]).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(Snapshot::read(&b"time 1\nbody 1 2 3\n"[..]).is_err());
+        assert!(Snapshot::read(&b"velocity 1 2\n"[..]).is_err());
+        assert!(Snapshot::read(&b"body 1 2 3 4 5 6 7 massive\n"[..]).is_err());
+        assert!(Snapshot::read(&b"body 1 2 3 4 5 6\n"[..]).is_err());
+        assert_eq!(Snapshot::read(&b"time 1\n\n"[..]).unwrap().seed(), None);
+    }
+}
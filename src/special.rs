@@ -0,0 +1,76 @@
+use std::f64::consts::PI;
+
+pub fn erf(x: f64) -> f64 {
+    if x.abs() < 2.0 {
+        erf_series(x)
+    } else {
+        x.signum() * (1.0 - erfc_continued_fraction(x.abs()))
+    }
+}
+
+pub fn erfc(x: f64) -> f64 {
+    if x < 2.0 {
+        1.0 - erf(x)
+    } else {
+        erfc_continued_fraction(x)
+    }
+}
+
+// Series with positive terms only, accurate to rounding for |x| < 2.
+fn erf_series(x: f64) -> f64 {
+    let mut term = x;
+    let mut sum = x;
+    let mut n = 0.0;
+
+    while term.abs() > 1e-17 * sum.abs() {
+        n += 1.0;
+        term *= 2.0 * x * x / (2.0 * n + 1.0);
+        sum += term;
+    }
+
+    2.0 / PI.sqrt() * (-x * x).exp() * sum
+}
+
+// Laplace's continued fraction, evaluated backwards from a fixed depth.
+fn erfc_continued_fraction(x: f64) -> f64 {
+    let mut fraction = x;
+    for n in (1..=60).rev() {
+        fraction = x + n as f64 / 2.0 / fraction;
+    }
+
+    (-x * x).exp() / (PI.sqrt() * fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculates_error_function() {
+        let expected = [
+            (0.0, 0.0),
+            (0.5, 0.520_499_877_813_046_5),
+            (1.0, 0.842_700_792_949_714_9),
+            (-2.0, -0.995_322_265_018_952_7),
+        ];
+
+        for &(x, value) in expected.iter() {
+            assert!((erf(x) - value).abs() < 1e-15, "erf({})", x);
+        }
+    }
+
+    #[test]
+    fn calculates_complementary_error_function() {
+        let expected = [
+            (-1.0, 1.842_700_792_949_715),
+            (2.5, 4.069_520_174_449_59e-4),
+            (3.0, 2.209_049_699_858_544e-5),
+            (5.0, 1.537_459_794_428_035e-12),
+            (10.0, 2.088_487_583_762_545e-45),
+        ];
+
+        for &(x, value) in expected.iter() {
+            assert!(((erfc(x) - value) / value).abs() < 1e-12, "erfc({})", x);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{body::Body, vec2::Vec2};
+
+/// Gravitational constant in SI units.
+pub const G: f64 = 6.67408e-11;
+pub const AU: f64 = 1.495978707e11;
+pub const SOLAR_MASS: f64 = 1.98892e30;
+pub const YEAR: f64 = 365.25 * 24.0 * 3600.0;
+
+/// Units of length, mass and time, each given in SI units.
+#[derive(PartialEq, Debug, Clone)]
+pub struct UnitSystem {
+    length: f64,
+    mass: f64,
+    time: f64,
+}
+
+impl UnitSystem {
+    pub fn new(length: f64, mass: f64, time: f64) -> UnitSystem {
+        UnitSystem { length, mass, time }
+    }
+
+    pub fn si() -> UnitSystem {
+        UnitSystem::new(1.0, 1.0, 1.0)
+    }
+
+    /// Astronomical units, solar masses and Julian years.
+    pub fn astronomical() -> UnitSystem {
+        UnitSystem::new(AU, SOLAR_MASS, YEAR)
+    }
+
+    /// Hénon's N-body units: the time unit is chosen so that `G = 1`.
+    pub fn nbody(length: f64, mass: f64) -> UnitSystem {
+        UnitSystem::new(length, mass, (length.powi(3) / (G * mass)).sqrt())
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn velocity(&self) -> f64 {
+        self.length / self.time
+    }
+
+    pub fn gravitational_constant(&self) -> f64 {
+        G * self.mass * self.time.powi(2) / self.length.powi(3)
+    }
+
+    pub fn convert_length(&self, length: f64, to: &UnitSystem) -> f64 {
+        length * self.length / to.length
+    }
+
+    pub fn convert_mass(&self, mass: f64, to: &UnitSystem) -> f64 {
+        mass * self.mass / to.mass
+    }
+
+    pub fn convert_time(&self, time: f64, to: &UnitSystem) -> f64 {
+        time * self.time / to.time
+    }
+
+    pub fn convert_velocity(&self, velocity: f64, to: &UnitSystem) -> f64 {
+        velocity * self.velocity() / to.velocity()
+    }
+
+    pub fn convert_body(&self, body: &Body, to: &UnitSystem) -> Body {
+        let convert_length = |v: &Vec2| {
+            Vec2::new(
+                self.convert_length(v.x(), to),
+                self.convert_length(v.y(), to),
+            )
+        };
+        let convert_velocity = |v: &Vec2| {
+            Vec2::new(
+                self.convert_velocity(v.x(), to),
+                self.convert_velocity(v.y(), to),
+            )
+        };
+
+        Body::new(
+            convert_length(body.pos()),
+            convert_velocity(body.velocity()),
+            self.convert_mass(body.mass(), to),
+        )
+        .with_radius(self.convert_length(body.radius(), to))
+        .with_charge(body.charge())
+        .with_test_particle(body.is_test_particle())
+    }
+
+    pub fn convert_bodies(&self, bodies: &[Body], to: &UnitSystem) -> Vec<Body> {
+        bodies.iter().map(|b| self.convert_body(b, to)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-12 * expected.abs(),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn calculates_gravitational_constant() {
+        assert_eq!(UnitSystem::si().gravitational_constant(), G);
+        assert_close(UnitSystem::nbody(3e20, 2e40).gravitational_constant(), 1.0);
+        // Kepler's third law for the Earth: G M = 4 pi^2 AU^3 / yr^2.
+        assert!((UnitSystem::astronomical().gravitational_constant() - 39.476).abs() < 0.01);
+    }
+
+    #[test]
+    fn converts_quantities() {
+        let si = UnitSystem::si();
+        let astronomical = UnitSystem::astronomical();
+
+        assert_close(astronomical.convert_length(2.0, &si), 2.0 * AU);
+        assert_close(si.convert_mass(SOLAR_MASS / 2.0, &astronomical), 0.5);
+        assert_close(astronomical.convert_time(1.0, &si), YEAR);
+        assert_close(astronomical.convert_velocity(1.0, &si), AU / YEAR);
+    }
+
+    #[test]
+    fn converts_bodies_back_and_forth() {
+        let nbody = UnitSystem::nbody(AU, SOLAR_MASS);
+        let body = Body::new(Vec2::new(1.0, -2.0), Vec2::new(0.5, 0.25), 3.0).with_radius(0.1);
+
+        let converted = nbody.convert_body(&body, &UnitSystem::si());
+        assert_close(converted.pos().y(), -2.0 * AU);
+        assert_close(converted.mass(), 3.0 * SOLAR_MASS);
+        assert_close(converted.velocity().x(), 0.5 * AU / nbody.time());
+
+        let round_trip = UnitSystem::si().convert_body(&converted, &nbody);
+        assert_close(round_trip.pos().x(), 1.0);
+        assert_close(round_trip.velocity().y(), 0.25);
+        assert_close(round_trip.radius(), 0.1);
+    }
+}
@@ -23,6 +23,20 @@ impl Vec2 {
         ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
     }
 
+    pub fn dot(&self, other: &Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn rotate(&self, angle: f64) -> Vec2 {
+        let (sin, cos) = angle.sin_cos();
+
+        Vec2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
     pub fn x(&self) -> f64 {
         self.x
     }
@@ -128,6 +142,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn calculates_dot_product() {
+        assert_eq!(Vec2::new(3.0, -2.0).dot(&Vec2::new(4.0, 1.5)), 9.0);
+    }
+
+    #[test]
+    fn calculates_length() {
+        assert_eq!(Vec2::new(3.0, -4.0).length(), 5.0);
+    }
+
+    #[test]
+    fn rotates_by_angle() {
+        let rotated = Vec2::new(2.0, 1.0).rotate(std::f64::consts::FRAC_PI_2);
+
+        assert!((rotated.x() + 1.0).abs() < 1e-15);
+        assert!((rotated.y() - 2.0).abs() < 1e-15);
+    }
+
     #[test]
     fn calculates_distance() {
         assert_eq!(